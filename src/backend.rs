@@ -0,0 +1,99 @@
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+
+use crate::continued_fraction::{Convergents, SqrtTerms};
+use crate::error::PellError;
+use crate::solver::{PellEquation, Solution, Solver};
+use crate::strategy::MultiplierStrategy;
+
+/// An algorithm that finds the fundamental solution of x^2 - N*y^2 = 1.
+pub trait PellBackend {
+    fn name(&self) -> &'static str;
+
+    fn solve(&self, eq: &PellEquation) -> Result<Solution, PellError>;
+}
+
+impl<S: MultiplierStrategy> PellBackend for Solver<S> {
+    fn name(&self) -> &'static str {
+        "chakravala"
+    }
+
+    fn solve(&self, eq: &PellEquation) -> Result<Solution, PellError> {
+        Solver::solve(self, eq)
+    }
+}
+
+/// Builds the fundamental solution from the convergents of the regular
+/// continued fraction of sqrt(N).
+///
+/// If the period has length l, then p_{l-1}^2 - N*q_{l-1}^2 = (-1)^l, so an odd
+/// period yields the negative solution and its square is the fundamental one.
+/// `steps` counts the partial quotients used after a0.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContinuedFraction;
+
+impl PellBackend for ContinuedFraction {
+    fn name(&self) -> &'static str {
+        "continued-fraction"
+    }
+
+    fn solve(&self, eq: &PellEquation) -> Result<Solution, PellError> {
+        let n = eq.n();
+        let root = eq.checked_root()?;
+        let end = &root * 2u32;
+
+        // p_{l-1} / q_{l-1} is the convergent just before the closing 2*a0.
+        let terms = SqrtTerms::new(n, &root).take_while(|a| *a != end);
+        let mut last = (BigInt::one(), BigInt::zero());
+        let mut period = 0;
+
+        for convergent in Convergents::new(terms) {
+            last = convergent;
+            period += 1;
+        }
+        let (p, q) = last;
+
+        // a0 was counted in place of the closing 2*a0.
+        if period % 2 == 0 {
+            Ok(Solution { x: p, y: q, steps: period, negative: None, shortcut: None })
+        } else {
+            let x = &p * &p + n * &q * &q;
+            let y = &p * &q * 2u32;
+            Ok(Solution { x, y, steps: period, negative: Some((p, q)), shortcut: None })
+        }
+    }
+}
+
+/// Runs two backends and fails with `BackendMismatch` unless they agree.
+/// Returns the first backend's solution.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Verify<A, B> {
+    pub first: A,
+    pub second: B,
+}
+
+impl<A: PellBackend, B: PellBackend> Verify<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Verify { first, second }
+    }
+}
+
+impl<A: PellBackend, B: PellBackend> PellBackend for Verify<A, B> {
+    fn name(&self) -> &'static str {
+        "verify"
+    }
+
+    fn solve(&self, eq: &PellEquation) -> Result<Solution, PellError> {
+        let first = self.first.solve(eq)?;
+        let second = self.second.solve(eq)?;
+
+        if first.x != second.x || first.y != second.y || first.negative != second.negative {
+            return Err(PellError::BackendMismatch {
+                n: eq.n().clone(),
+                first: self.first.name(),
+                second: self.second.name(),
+            });
+        }
+        Ok(first)
+    }
+}
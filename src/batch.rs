@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use num_integer::Roots;
+
+use crate::error::PellError;
+use crate::solver::{PellEquation, Solution, Solver};
+use crate::strategy::MultiplierStrategy;
+
+/// The outcome of solving one N of a range.
+#[derive(Debug, Clone)]
+pub struct RangeEntry {
+    pub n: u64,
+    pub result: Result<Solution, PellError>,
+    /// Time spent solving this N alone.
+    pub elapsed: Duration,
+}
+
+impl<S: MultiplierStrategy + Clone + Send + 'static> Solver<S> {
+    /// Solves every non-square N in start..end on a pool of threads.
+    ///
+    /// Entries come back in N order as soon as they and every smaller N are
+    /// done. A failure is reported in its entry and does not stop the run.
+    pub fn solve_range(&self, start: u64, end: u64) -> RangeSolutions {
+        let threads = self
+            .threads
+            .or_else(|| thread::available_parallelism().ok().map(|t| t.get()))
+            .unwrap_or(1)
+            .max(1);
+
+        let next = Arc::new(AtomicU64::new(start));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = sync_channel(threads * 4);
+
+        for _ in 0..threads {
+            let (solver, next, stop, sender) = (self.clone(), next.clone(), stop.clone(), sender.clone());
+            thread::spawn(move || work(solver, end, &next, &stop, sender));
+        }
+
+        RangeSolutions {
+            next: start,
+            end,
+            pending: BTreeMap::new(),
+            receiver,
+            stop,
+        }
+    }
+}
+
+/// Takes the next unsolved N until the range is exhausted or the receiver is gone.
+fn work<S: MultiplierStrategy>(solver: Solver<S>, end: u64, next: &AtomicU64, stop: &AtomicBool, sender: SyncSender<RangeEntry>) {
+    while !stop.load(Ordering::Relaxed) {
+        let n = next.fetch_add(1, Ordering::Relaxed);
+        if n >= end {
+            break;
+        }
+        if is_square(n) {
+            continue;
+        }
+
+        let started = Instant::now();
+        let result = solver.solve(&PellEquation::new(n));
+        let entry = RangeEntry { n, result, elapsed: started.elapsed() };
+        if sender.send(entry).is_err() {
+            break;
+        }
+    }
+}
+
+fn is_square(n: u64) -> bool {
+    let root = n.sqrt();
+    root * root == n
+}
+
+/// Iterator returned by `Solver::solve_range`. Dropping it stops the workers
+/// once they finish the N they are on.
+#[derive(Debug)]
+pub struct RangeSolutions {
+    next: u64,
+    end: u64,
+    // Entries that finished ahead of a smaller N still being solved.
+    pending: BTreeMap<u64, RangeEntry>,
+    receiver: Receiver<RangeEntry>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Iterator for RangeSolutions {
+    type Item = RangeEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next < self.end && is_square(self.next) {
+            self.next += 1;
+        }
+        if self.next >= self.end {
+            return None;
+        }
+
+        let entry = loop {
+            if let Some(entry) = self.pending.remove(&self.next) {
+                break entry;
+            }
+            let entry = self.receiver.recv().expect("a worker panicked");
+            self.pending.insert(entry.n, entry);
+        };
+
+        self.next += 1;
+        Some(entry)
+    }
+}
+
+impl Drop for RangeSolutions {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,143 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive};
+
+use crate::error::{PartialState, PellError};
+use crate::quadratic::QuadInt;
+use crate::solver::{PellEquation, Solver, closest_root, pick_in_class};
+use crate::strategy::MultiplierStrategy;
+
+/// The fundamental solution kept as a product of small quadratic integers,
+/// x1 + y1*sqrt(N) = (a0 + sqrt(N)) * prod (m_i + sqrt(N)) / |k_i|, where a0
+/// starts the cycle and (m_i, k_i) are the multiplier and k of each step.
+/// Every m_i and k_i is below 2*sqrt(N), so this takes O(steps) small numbers
+/// even when x1 has hundreds of thousands of digits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactUnit {
+    n: BigInt,
+    a0: BigInt,
+    /// (m_i, |k_i|) for each step.
+    factors: Vec<(BigInt, BigInt)>,
+}
+
+impl CompactUnit {
+    pub fn n(&self) -> &BigInt {
+        &self.n
+    }
+
+    pub fn a0(&self) -> &BigInt {
+        &self.a0
+    }
+
+    /// (m_i, |k_i|) for each step of the cycle.
+    pub fn factors(&self) -> &[(BigInt, BigInt)] {
+        &self.factors
+    }
+
+    pub fn steps(&self) -> usize {
+        self.factors.len()
+    }
+
+    /// log(x1 + y1*sqrt(N)), summed in floating point from the factors.
+    pub fn approx_regulator(&self) -> f64 {
+        let sqrt_n = to_f64(&self.n).sqrt();
+        let start = (to_f64(&self.a0) + sqrt_n).ln();
+
+        self.factors
+            .iter()
+            .map(|(m, k)| ((to_f64(m) + sqrt_n) / to_f64(k)).ln())
+            .fold(start, |sum, term| sum + term)
+    }
+
+    /// Approximate number of decimal digits of x1, since x1 + y1*sqrt(N) is close to 2*x1.
+    pub fn digits(&self) -> u64 {
+        let log_x = self.approx_regulator() / std::f64::consts::LN_10 - std::f64::consts::LOG10_2;
+        log_x.max(0.0) as u64 + 1
+    }
+
+    /// Expands to (x1, y1). The numerator and the denominator are each
+    /// multiplied out by binary splitting, then divided once.
+    pub fn expand(&self) -> (BigInt, BigInt) {
+        let mut terms = vec![QuadInt::new(self.a0.clone(), 1, self.n.clone())];
+        terms.extend(self.factors.iter().map(|(m, _)| QuadInt::new(m.clone(), 1, self.n.clone())));
+
+        let k: Vec<&BigInt> = self.factors.iter().map(|(_, k)| k).collect();
+        let QuadInt { a: x, b: y, .. } = product(&self.n, &terms) / &product_of(&k);
+        (x, y)
+    }
+}
+
+impl<S: MultiplierStrategy> Solver<S> {
+    /// Runs the cycle keeping only m and k, and returns the fundamental
+    /// solution in compact form.
+    ///
+    /// Since a + b*m = 0 (mod |k|) carries over to the next triple as m' = -m
+    /// (mod |k'|), the next multiplier is found without a or b.
+    pub fn solve_compact(&self, eq: &PellEquation) -> Result<CompactUnit, PellError> {
+        let root = eq.checked_root()?;
+        let n = eq.n();
+
+        let a0 = closest_root(n, root.clone());
+        let mut k = &a0 * &a0 - n;
+        let mut m = a0.clone();
+        let mut factors = Vec::new();
+
+        while !k.is_one() {
+            if let Some(stop) = self.options.stop(factors.len()) {
+                // Only m and k are kept, so a and b are multiplied out from the steps so far.
+                let steps = factors.len();
+                let (a, b) = CompactUnit { n: n.clone(), a0, factors }.expand();
+                return Err(stop.error(PartialState { a, b, k, steps }));
+            }
+
+            let abs_k = k.abs();
+            (m, k) = compact_step(&self.strategy, n, &root, &m, &k);
+            factors.push((m.clone(), abs_k));
+        }
+
+        Ok(CompactUnit { n: n.clone(), a0, factors })
+    }
+}
+
+/// One step of the cycle on m and k alone: the next multiplier, picked from
+/// the class of -m modulo |k|, and the k it produces.
+pub(crate) fn compact_step<S: MultiplierStrategy>(
+    strategy: &S,
+    n: &BigInt,
+    root: &BigInt,
+    m: &BigInt,
+    k: &BigInt,
+) -> (BigInt, BigInt) {
+    let abs_k = k.abs();
+    let m = pick_in_class(strategy, n, root, k, &(-m).mod_floor(&abs_k), &abs_k).expect("BigInt cannot overflow");
+    let k = (&m * &m - n) / k;
+    (m, k)
+}
+
+/// Product of elements x + y*sqrt(N) by binary splitting.
+fn product(n: &BigInt, terms: &[QuadInt]) -> QuadInt {
+    match terms {
+        [] => QuadInt::one(n.clone()),
+        [single] => single.clone(),
+        _ => {
+            let (left, right) = terms.split_at(terms.len() / 2);
+            product(n, left) * product(n, right)
+        }
+    }
+}
+
+/// Product of integers by binary splitting.
+fn product_of(values: &[&BigInt]) -> BigInt {
+    match values {
+        [] => BigInt::one(),
+        [single] => (*single).clone(),
+        _ => {
+            let (left, right) = values.split_at(values.len() / 2);
+            product_of(left) * product_of(right)
+        }
+    }
+}
+
+fn to_f64(value: &BigInt) -> f64 {
+    value.to_f64().unwrap_or(f64::INFINITY)
+}
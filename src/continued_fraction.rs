@@ -0,0 +1,357 @@
+//! Continued fraction expansions of sqrt(N) and of quadratic irrationals (P + sqrt(D)) / Q.
+
+use std::collections::HashMap;
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, Zero};
+
+use crate::error::PellError;
+use crate::solver::PellEquation;
+
+/// A periodic continued fraction [pre_period; (period)], repeating `period` forever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeriodicExpansion {
+    pub pre_period: Vec<BigInt>,
+    pub period: Vec<BigInt>,
+}
+
+impl PeriodicExpansion {
+    pub fn period_len(&self) -> usize {
+        self.period.len()
+    }
+
+    /// True if the period, without its final term, reads the same in both
+    /// directions. This always holds for sqrt(N).
+    pub fn is_palindrome(&self) -> bool {
+        let body = &self.period[..self.period.len().saturating_sub(1)];
+        body.iter().eq(body.iter().rev())
+    }
+
+    /// All partial quotients: the pre-period, then the period repeated forever.
+    pub fn terms(&self) -> impl Iterator<Item = BigInt> + '_ {
+        self.pre_period.iter().chain(self.period.iter().cycle()).cloned()
+    }
+
+    /// The convergents p_i / q_i of the expansion.
+    pub fn convergents(&self) -> Convergents<impl Iterator<Item = BigInt> + '_> {
+        Convergents::new(self.terms())
+    }
+}
+
+/// Returns the expansion of sqrt(N) as [a0; (a1, ..., a_{l-1}, 2*a0)].
+pub fn sqrt_expansion(n: impl Into<BigInt>) -> Result<PeriodicExpansion, PellError> {
+    let eq = PellEquation::new(n);
+    let root = eq.checked_root()?;
+    let end = &root * 2u32;
+
+    let mut terms = SqrtTerms::new(eq.n(), &root);
+    let a0 = terms.next().expect("the expansion is infinite");
+
+    let mut period = Vec::new();
+    for a in terms {
+        let last = a == end;
+        period.push(a);
+        if last {
+            break;
+        }
+    }
+
+    Ok(PeriodicExpansion { pre_period: vec![a0], period })
+}
+
+/// The quadratic irrational (P + sqrt(D)) / Q.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuadraticIrrational {
+    p: BigInt,
+    q: BigInt,
+    d: BigInt,
+    root: BigInt,
+}
+
+impl QuadraticIrrational {
+    /// D must be a positive non-square and Q must be nonzero.
+    pub fn new(p: impl Into<BigInt>, q: impl Into<BigInt>, d: impl Into<BigInt>) -> Result<Self, PellError> {
+        let (mut p, mut q, mut d) = (p.into(), q.into(), d.into());
+        if q.is_zero() {
+            return Err(PellError::ZeroDenominator);
+        }
+        PellEquation::new(d.clone()).checked_root()?;
+
+        // The recurrence needs Q | D - P^2. If that fails, scale by |Q|:
+        // (P + sqrt(D)) / Q = (P|Q| + sqrt(D Q^2)) / (Q|Q|).
+        if !(&d - &p * &p).is_multiple_of(&q) {
+            let abs_q = q.abs();
+            p *= &abs_q;
+            d *= &abs_q * &abs_q;
+            q *= abs_q;
+        }
+
+        let root = d.sqrt();
+        Ok(QuadraticIrrational { p, q, d, root })
+    }
+
+    /// The partial quotients, without end.
+    pub fn terms(&self) -> impl Iterator<Item = BigInt> + '_ {
+        let (mut p, mut q) = (self.p.clone(), self.q.clone());
+        std::iter::repeat_with(move || {
+            let a = partial_quotient(&p, &self.root, &q);
+            p = &a * &q - &p;
+            q = (&self.d - &p * &p) / &q;
+            a
+        })
+    }
+
+    /// Expands until a complete quotient repeats, which Lagrange's theorem guarantees.
+    pub fn expand(&self) -> PeriodicExpansion {
+        let (mut p, mut q) = (self.p.clone(), self.q.clone());
+        let mut seen = HashMap::new();
+        let mut terms = Vec::new();
+
+        let start = loop {
+            if let Some(&index) = seen.get(&(p.clone(), q.clone())) {
+                break index;
+            }
+            seen.insert((p.clone(), q.clone()), terms.len());
+
+            let a = partial_quotient(&p, &self.root, &q);
+            p = &a * &q - &p;
+            q = (&self.d - &p * &p) / &q;
+            terms.push(a);
+        };
+
+        let period = terms.split_off(start);
+        PeriodicExpansion { pre_period: terms, period }
+    }
+}
+
+/// Convergents p_i / q_i of a sequence of partial quotients.
+#[derive(Debug, Clone)]
+pub struct Convergents<I> {
+    terms: I,
+    p: (BigInt, BigInt),
+    q: (BigInt, BigInt),
+}
+
+impl<I: Iterator<Item = BigInt>> Convergents<I> {
+    pub fn new(terms: I) -> Self {
+        // (p_{-2}, p_{-1}) = (0, 1), (q_{-2}, q_{-1}) = (1, 0)
+        Convergents {
+            terms,
+            p: (BigInt::zero(), BigInt::one()),
+            q: (BigInt::one(), BigInt::zero()),
+        }
+    }
+}
+
+impl<I: Iterator<Item = BigInt>> Iterator for Convergents<I> {
+    type Item = (BigInt, BigInt);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let a = self.terms.next()?;
+        let p = &a * &self.p.1 + &self.p.0;
+        let q = &a * &self.q.1 + &self.q.0;
+
+        self.p = (std::mem::replace(&mut self.p.1, p.clone()), p.clone());
+        self.q = (std::mem::replace(&mut self.q.1, q.clone()), q.clone());
+        Some((p, q))
+    }
+}
+
+/// Best rational approximations p / q of sqrt(N): each is strictly closer to
+/// sqrt(N) than every fraction with a smaller or equal denominator before it.
+/// These are the convergents together with some semiconvergents.
+pub fn best_approximations(n: impl Into<BigInt>) -> Result<BestApproximations, PellError> {
+    let eq = PellEquation::new(n);
+    let root = eq.checked_root()?;
+    let mut terms = SqrtTerms::new(eq.n(), &root);
+    let a0 = terms.next().expect("the expansion is infinite");
+
+    Ok(BestApproximations {
+        n: eq.n().clone(),
+        terms,
+        prev: (BigInt::one(), BigInt::zero()),
+        current: (a0.clone(), BigInt::one()),
+        a: BigInt::zero(),
+        j: BigInt::zero(),
+        best: None,
+    })
+}
+
+/// Iterator returned by `best_approximations`.
+#[derive(Debug, Clone)]
+pub struct BestApproximations {
+    n: BigInt,
+    terms: SqrtTerms,
+    // (p_{k-1}, q_{k-1}) and (p_k, q_k)
+    prev: (BigInt, BigInt),
+    current: (BigInt, BigInt),
+    // Semiconvergents (p_{k-1} + j*p_k) / (q_{k-1} + j*q_k) for j up to a = a_{k+1}
+    a: BigInt,
+    j: BigInt,
+    best: Option<(BigInt, BigInt)>,
+}
+
+impl Iterator for BestApproximations {
+    type Item = (BigInt, BigInt);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.best.is_none() {
+            // The nearest integer comes first, which may be a0 + 1.
+            let above = (&self.current.0 + 1u32, BigInt::one());
+            let first = if closer_to_root(&self.n, &above, &self.current) { above } else { self.current.clone() };
+            self.best = Some(first.clone());
+            return Some(first);
+        }
+
+        loop {
+            if self.j >= self.a {
+                // Move on to the next convergent. Semiconvergents with j < a/2
+                // are never closer than p_k / q_k, so start halfway.
+                if !self.a.is_zero() {
+                    let next = (
+                        &self.a * &self.current.0 + &self.prev.0,
+                        &self.a * &self.current.1 + &self.prev.1,
+                    );
+                    self.prev = std::mem::replace(&mut self.current, next);
+                }
+                self.a = self.terms.next()?;
+                self.j = (&self.a / 2u32).max(BigInt::one()) - 1u32;
+            }
+
+            self.j += 1;
+            let candidate = (
+                &self.prev.0 + &self.j * &self.current.0,
+                &self.prev.1 + &self.j * &self.current.1,
+            );
+
+            let best = self.best.as_ref().expect("set on the first call");
+            if closer_to_root(&self.n, &candidate, best) {
+                self.best = Some(candidate.clone());
+                return Some(candidate);
+            }
+        }
+    }
+}
+
+/// True if p1/q1 is strictly closer to sqrt(N) than p2/q2 (all values non-negative).
+fn closer_to_root(n: &BigInt, (p1, q1): &(BigInt, BigInt), (p2, q2): &(BigInt, BigInt)) -> bool {
+    // Compare |A - B*sqrt(N)| with |C - B*sqrt(N)| over the common denominator B.
+    let a = p1 * q2;
+    let c = p2 * q1;
+    let b = q1 * q2;
+    let nb2 = n * &b * &b;
+
+    let a_above = &a * &a > nb2;
+    let c_above = &c * &c > nb2;
+    let sum = &a + &c;
+
+    match (a_above, c_above) {
+        (true, true) => a < c,
+        (false, false) => a > c,
+        (true, false) => &sum * &sum < &nb2 * 4u32,
+        (false, true) => &sum * &sum > &nb2 * 4u32,
+    }
+}
+
+/// floor((P + sqrt(D)) / Q) for a non-square D with `root` = floor(sqrt(D)).
+pub(crate) fn partial_quotient(p: &BigInt, root: &BigInt, q: &BigInt) -> BigInt {
+    if q.is_positive() {
+        (p + root).div_floor(q)
+    } else {
+        (p + root + 1u32).div_floor(q)
+    }
+}
+
+/// Partial quotients of sqrt(N) for a non-square N: a0 followed by the
+/// terms of the period, which always ends with 2*a0.
+#[derive(Debug, Clone)]
+pub(crate) struct SqrtTerms {
+    n: BigInt,
+    a0: BigInt,
+    m: BigInt,
+    d: BigInt,
+    a: BigInt,
+    started: bool,
+}
+
+impl SqrtTerms {
+    /// `root` must be floor(sqrt(N)).
+    pub(crate) fn new(n: &BigInt, root: &BigInt) -> Self {
+        SqrtTerms {
+            n: n.clone(),
+            a0: root.clone(),
+            m: BigInt::from(0),
+            d: BigInt::from(1),
+            a: root.clone(),
+            started: false,
+        }
+    }
+}
+
+impl Iterator for SqrtTerms {
+    type Item = BigInt;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            return Some(self.a.clone());
+        }
+
+        // m' = d*a - m, d' = (N - m'^2) / d, a' = floor((a0 + m') / d')
+        self.m = &self.d * &self.a - &self.m;
+        self.d = (&self.n - &self.m * &self.m) / &self.d;
+        self.a = (&self.a0 + &self.m).div_floor(&self.d);
+        Some(self.a.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(values: &[i64]) -> Vec<BigInt> {
+        values.iter().map(|&v| BigInt::from(v)).collect()
+    }
+
+    fn pairs(values: &[(i64, i64)]) -> Vec<(BigInt, BigInt)> {
+        values.iter().map(|&(p, q)| (BigInt::from(p), BigInt::from(q))).collect()
+    }
+
+    #[test]
+    fn sqrt_61() {
+        let expansion = sqrt_expansion(61).unwrap();
+        assert_eq!(expansion.pre_period, ints(&[7]));
+        assert_eq!(expansion.period, ints(&[1, 4, 3, 1, 2, 2, 1, 3, 4, 1, 14]));
+        assert!(expansion.is_palindrome());
+        assert!(sqrt_expansion(64).is_err());
+    }
+
+    #[test]
+    fn quadratic_irrational_with_negative_q() {
+        // (2 + sqrt(7)) / -5 = -0.929...
+        let expansion = QuadraticIrrational::new(2, -5, 7).unwrap().expand();
+        assert_eq!(expansion.pre_period, ints(&[-1, 14]));
+        assert_eq!(expansion.period, ints(&[8, 1, 2, 1, 8, 13]));
+
+        let convergents: Vec<_> = expansion.convergents().take(4).collect();
+        assert_eq!(convergents, pairs(&[(-1, 1), (-13, 14), (-105, 113), (-118, 127)]));
+        assert_eq!(QuadraticIrrational::new(2, 0, 7), Err(PellError::ZeroDenominator));
+    }
+
+    #[test]
+    fn convergents_of_sqrt_2() {
+        let convergents: Vec<_> = sqrt_expansion(2).unwrap().convergents().take(5).collect();
+        assert_eq!(convergents, pairs(&[(1, 1), (3, 2), (7, 5), (17, 12), (41, 29)]));
+    }
+
+    #[test]
+    fn best_approximations_include_semiconvergents() {
+        // 4/3 and 24/17 are semiconvergents of sqrt(2); sqrt(7) starts with 3/1, above a0.
+        let sqrt_2: Vec<_> = best_approximations(2).unwrap().take(7).collect();
+        assert_eq!(sqrt_2, pairs(&[(1, 1), (3, 2), (4, 3), (7, 5), (17, 12), (24, 17), (41, 29)]));
+
+        let sqrt_7: Vec<_> = best_approximations(7).unwrap().take(7).collect();
+        assert_eq!(sqrt_7, pairs(&[(3, 1), (5, 2), (8, 3), (21, 8), (29, 11), (37, 14), (45, 17)]));
+    }
+}
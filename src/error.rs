@@ -0,0 +1,75 @@
+use std::fmt;
+
+use num_bigint::BigInt;
+
+/// How far a run got before it was stopped: the triple (a, b, k) with
+/// a^2 - N*b^2 = k after `steps` steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialState {
+    pub a: BigInt,
+    pub b: BigInt,
+    pub k: BigInt,
+    pub steps: usize,
+}
+
+/// Errors returned by the solver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PellError {
+    /// N is a perfect square, so x^2 - N*y^2 = 1 only has the trivial solution (1, 0).
+    PerfectSquare { n: BigInt, root: BigInt },
+    /// N must be a positive integer.
+    ZeroOrNegativeN { n: BigInt },
+    /// The cycle did not reach k = 1 within the configured number of steps.
+    IterationLimitExceeded { state: PartialState },
+    /// The deadline passed before the cycle reached k = 1.
+    DeadlineExceeded { state: PartialState },
+    /// The run was cancelled through its `CancelToken`.
+    Cancelled { state: PartialState },
+    /// No m with (a + b*m) divisible by k was found.
+    NoValidMultiplier { a: BigInt, b: BigInt, k: BigInt },
+    /// x^2 - N*y^2 = 0 has no solution other than (0, 0).
+    ZeroConstant,
+    /// A quadratic irrational (P + sqrt(D)) / Q needs Q != 0.
+    ZeroDenominator,
+    /// The field Q(sqrt(d)) needs a squarefree d.
+    NotSquarefree { d: BigInt },
+    /// d is too large for the squarefree check by trial division.
+    SquarefreeUnknown { d: BigInt },
+    /// Modular results need a positive modulus.
+    InvalidModulus { modulus: BigInt },
+    /// Two backends returned different fundamental solutions.
+    BackendMismatch { n: BigInt, first: &'static str, second: &'static str },
+}
+
+impl fmt::Display for PellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PellError::PerfectSquare { n, root } => {
+                write!(f, "N={} is a perfect square ({}^2), only the trivial solution exists", n, root)
+            }
+            PellError::ZeroOrNegativeN { n } => write!(f, "N={} must be positive", n),
+            PellError::IterationLimitExceeded { state } => {
+                write!(f, "no solution found within {} steps", state.steps)
+            }
+            PellError::DeadlineExceeded { state } => {
+                write!(f, "deadline passed after {} steps", state.steps)
+            }
+            PellError::Cancelled { state } => write!(f, "cancelled after {} steps", state.steps),
+            PellError::NoValidMultiplier { a, b, k } => {
+                write!(f, "no valid m for triple a={}, b={}, k={}", a, b, k)
+            }
+            PellError::ZeroConstant => write!(f, "c must be nonzero"),
+            PellError::ZeroDenominator => write!(f, "Q must be nonzero"),
+            PellError::NotSquarefree { d } => write!(f, "d={} is not squarefree", d),
+            PellError::SquarefreeUnknown { d } => write!(f, "d={} is too large to check for square factors", d),
+            PellError::InvalidModulus { modulus } => {
+                write!(f, "modulus {} must be positive", modulus)
+            }
+            PellError::BackendMismatch { n, first, second } => {
+                write!(f, "{} and {} disagree for N={}", first, second, n)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PellError {}
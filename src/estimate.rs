@@ -0,0 +1,47 @@
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::error::PellError;
+use crate::solver::{PellEquation, Solver};
+use crate::strategy::MultiplierStrategy;
+
+/// Predicted size of the fundamental solution, made before the full solve.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate {
+    /// Exact number of steps in the full Chakravala cycle. A shortcut may
+    /// let the solver stop sooner.
+    pub steps: usize,
+    /// Approximate regulator log(x1 + y1*sqrt(N)).
+    pub regulator: f64,
+    /// Predicted number of decimal digits of x1 and y1.
+    pub digits_x: u64,
+    pub digits_y: u64,
+}
+
+/// Predicts the size of the fundamental solution without computing it, with
+/// the default solver.
+pub fn estimate(n: impl Into<BigInt>) -> Result<Estimate, PellError> {
+    Solver::new().estimate(&PellEquation::new(n))
+}
+
+impl<S: MultiplierStrategy> Solver<S> {
+    /// Predicts the size of the fundamental solution without computing it.
+    ///
+    /// This runs the cycle in compact form, where every value stays below
+    /// 2*sqrt(N), and sums the logarithms of the factors to get the regulator.
+    pub fn estimate(&self, eq: &PellEquation) -> Result<Estimate, PellError> {
+        let unit = self.solve_compact(eq)?;
+        let regulator = unit.approx_regulator();
+
+        // x1 + y1*sqrt(N) is close to 2*x1 and to 2*y1*sqrt(N).
+        let log_x = regulator / std::f64::consts::LN_10 - std::f64::consts::LOG10_2;
+        let log_y = log_x - eq.n().to_f64().unwrap_or(f64::INFINITY).log10() / 2.0;
+
+        Ok(Estimate {
+            steps: unit.steps(),
+            regulator,
+            digits_x: unit.digits(),
+            digits_y: log_y.max(0.0) as u64 + 1,
+        })
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, Zero};
+
+use crate::continued_fraction::partial_quotient;
+use crate::error::PellError;
+use crate::quadratic::QuadInt;
+use crate::solver::{PellEquation, Solver};
+use crate::strategy::MultiplierStrategy;
+
+/// All fundamental solutions of x^2 - N*y^2 = c.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneralSolution {
+    pub c: BigInt,
+    /// One fundamental solution per class. Empty when the equation has no solution.
+    pub classes: Vec<SolutionClass>,
+    /// Fundamental solution of x^2 - N*y^2 = 1 that generates every class.
+    pub unit: (BigInt, BigInt),
+}
+
+/// Fundamental solution of one class of x^2 - N*y^2 = c.
+///
+/// The representative has the least non-negative y in its class; x may be negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolutionClass {
+    pub x: BigInt,
+    pub y: BigInt,
+    unit: QuadInt,
+}
+
+impl SolutionClass {
+    /// Iterates over +-(x, y) * (x1 + y1*sqrt(N))^j for j = 0, 1, 2, ..., with
+    /// the sign that makes x non-negative.
+    pub fn iter(&self) -> ClassSolutions {
+        ClassSolutions {
+            current: QuadInt::new(self.x.clone(), self.y.clone(), self.unit.n().clone()),
+            unit: self.unit.clone(),
+        }
+    }
+}
+
+/// Infinite family of solutions belonging to one class.
+#[derive(Debug, Clone)]
+pub struct ClassSolutions {
+    current: QuadInt,
+    unit: QuadInt,
+}
+
+impl Iterator for ClassSolutions {
+    type Item = (BigInt, BigInt);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = &self.current * &self.unit;
+        let current = std::mem::replace(&mut self.current, next);
+        let QuadInt { a, b, .. } = if current.a.is_negative() { -current } else { current };
+        Some((a, b))
+    }
+}
+
+impl<S: MultiplierStrategy> Solver<S> {
+    /// Finds every class of solutions of x^2 - N*y^2 = c, using the
+    /// Lagrange-Matthews-Mollin reduction to continued fractions.
+    pub fn solve_general(&self, eq: &PellEquation, c: impl Into<BigInt>) -> Result<GeneralSolution, PellError> {
+        let c = c.into();
+        if c.is_zero() {
+            return Err(PellError::ZeroConstant);
+        }
+
+        let fundamental = self.solve(eq)?;
+        let n = eq.n();
+        let root = n.sqrt();
+        let unit = QuadInt::new(fundamental.x, fundamental.y, n.clone());
+
+        let mut classes: Vec<SolutionClass> = Vec::new();
+
+        // 1. Every solution is f*(r, s) with gcd(r, s) = 1 and r^2 - N*s^2 = c / f^2.
+        let mut f = BigInt::zero();
+        loop {
+            f += 1;
+            if &f * &f > c.abs() {
+                break;
+            }
+            let (m, rem) = c.div_rem(&(&f * &f));
+            if !rem.is_zero() {
+                continue;
+            }
+            let abs_m = m.abs();
+
+            // 2. Primitive solutions correspond to z^2 = N (mod |m|), -|m|/2 < z <= |m|/2.
+            let mut z = -(&abs_m - 1u32) / 2u32;
+            while &z * 2u32 <= abs_m {
+                if (&z * &z - n).mod_floor(&abs_m).is_zero()
+                    && let Some(primitive) = primitive_solution(n, &root, &z, &m, fundamental.negative.as_ref())
+                {
+                    let QuadInt { a: x, b: y, .. } = reduce(&unit, &primitive * &QuadInt::new(f.clone(), 0, n.clone()));
+                    if !classes.iter().any(|cl| cl.x == x && cl.y == y) {
+                        classes.push(SolutionClass { x, y, unit: unit.clone() });
+                    }
+                }
+                z += 1;
+            }
+        }
+
+        classes.sort_by(|a, b| (&a.y, &a.x).cmp(&(&b.y, &b.x)));
+        Ok(GeneralSolution { c, classes, unit: (unit.a, unit.b) })
+    }
+}
+
+/// Primitive solution of r^2 - N*s^2 = m belonging to the root z, if any.
+fn primitive_solution(
+    n: &BigInt,
+    root: &BigInt,
+    z: &BigInt,
+    m: &BigInt,
+    negative: Option<&(BigInt, BigInt)>,
+) -> Option<QuadInt> {
+    let (r, s) = pqa_search(n, root, z, &m.abs())?;
+    let found = QuadInt::new(r, s, n.clone());
+    if found.norm() == *m {
+        return Some(found);
+    }
+
+    // The expansion found -m instead; a solution of the negative equation
+    // moves it back to m.
+    let (t, u) = negative?;
+    Some(found * QuadInt::new(t.clone(), u.clone(), n.clone()))
+}
+
+/// Runs the PQa expansion of (P0 + sqrt(N)) / Q0 until Q_i = +-1 and returns
+/// (G_{i-1}, B_{i-1}), or None if a full period passes without one.
+fn pqa_search(n: &BigInt, root: &BigInt, p0: &BigInt, q0: &BigInt) -> Option<(BigInt, BigInt)> {
+    let (mut p, mut q) = (p0.clone(), q0.clone());
+    let (mut g_prev, mut g) = (-p0, q0.clone());
+    let (mut b_prev, mut b) = (BigInt::one(), BigInt::zero());
+    let mut seen = HashSet::new();
+
+    loop {
+        // a_i = floor((P_i + sqrt(N)) / Q_i)
+        let a = partial_quotient(&p, root, &q);
+
+        let g_next = &a * &g + &g_prev;
+        g_prev = std::mem::replace(&mut g, g_next);
+        let b_next = &a * &b + &b_prev;
+        b_prev = std::mem::replace(&mut b, b_next);
+
+        p = &a * &q - &p;
+        q = (n - &p * &p) / &q;
+
+        if q.abs().is_one() {
+            return Some((g, b));
+        }
+        if !seen.insert((p.clone(), q.clone())) {
+            return None;
+        }
+    }
+}
+
+/// Moves x + y*sqrt(N) to the member of its class with the least non-negative y.
+fn reduce(unit: &QuadInt, value: QuadInt) -> QuadInt {
+    let inverse = unit.conjugate();
+
+    // |y| is convex along the class, so walk downhill in either direction.
+    let mut best = value;
+    for step in [&inverse, unit] {
+        loop {
+            let next = &best * step;
+            if next.b.abs() >= best.b.abs() {
+                break;
+            }
+            best = next;
+        }
+    }
+
+    // x + y*sqrt(N) and -(x + y*sqrt(N)) lie in the same class. An ambiguous
+    // class has two members with the least |y|; keep the one with larger x.
+    let least = best.b.abs();
+    [&best * &inverse, &best * unit, best]
+        .into_iter()
+        .filter(|q| q.b.abs() == least)
+        .map(|q| {
+            if q.b.is_negative() || (q.b.is_zero() && q.a.is_negative()) { -q } else { q }
+        })
+        .max_by(|p, q| p.a.cmp(&q.a))
+        .expect("the starting member is always kept")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classes_yield_every_positive_solution() {
+        for n in 2i64..30 {
+            let eq = PellEquation::new(n);
+            if eq.checked_root().is_err() {
+                continue;
+            }
+            for c in (-20i64..=20).filter(|c| *c != 0) {
+                let general = Solver::new().solve_general(&eq, c).unwrap();
+                let yielded: Vec<(BigInt, BigInt)> = general.classes.iter().flat_map(|class| class.iter().take(6)).collect();
+                for (x, y) in &yielded {
+                    assert!(!x.is_negative(), "N={} c={}: ({}, {})", n, c, x, y);
+                    assert_eq!(x * x - n * y * y, BigInt::from(c), "N={} c={}", n, c);
+                }
+
+                // Brute force over 0 < y < 100 finds nothing the classes miss.
+                for y in 1i64..100 {
+                    let x2 = c + n * y * y;
+                    let x = x2.max(0).isqrt();
+                    if x > 0 && x * x == x2 {
+                        let solution = (BigInt::from(x), BigInt::from(y));
+                        assert!(yielded.contains(&solution), "N={} c={}: ({}, {})", n, c, x, y);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn negative_representatives_yield_positive_solutions() {
+        let eq = PellEquation::new(5);
+        let general = Solver::new().solve_general(&eq, 4).unwrap();
+        let class = general.classes.iter().find(|class| class.x == BigInt::from(-3)).unwrap();
+        let solutions: Vec<(BigInt, BigInt)> = class.iter().skip(1).take(2).collect();
+        assert_eq!(solutions, [(BigInt::from(7), BigInt::from(3)), (BigInt::from(123), BigInt::from(55))]);
+    }
+}
+
@@ -0,0 +1,46 @@
+use std::fmt::Debug;
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Signed, ToPrimitive};
+
+/// Integer type the Chakravala cycle can run on.
+///
+/// The solver starts on i128 and moves to BigInt at the first step whose
+/// checked arithmetic would overflow, so small N never allocate.
+pub trait CycleInt: Integer + Signed + Clone + CheckedAdd + CheckedSub + CheckedMul + CheckedDiv + Debug {
+    /// Converts from BigInt, or None if the value does not fit.
+    fn from_bigint(value: &BigInt) -> Option<Self>;
+
+    fn to_bigint(&self) -> BigInt;
+}
+
+impl CycleInt for i64 {
+    fn from_bigint(value: &BigInt) -> Option<Self> {
+        value.to_i64()
+    }
+
+    fn to_bigint(&self) -> BigInt {
+        BigInt::from(*self)
+    }
+}
+
+impl CycleInt for i128 {
+    fn from_bigint(value: &BigInt) -> Option<Self> {
+        value.to_i128()
+    }
+
+    fn to_bigint(&self) -> BigInt {
+        BigInt::from(*self)
+    }
+}
+
+impl CycleInt for BigInt {
+    fn from_bigint(value: &BigInt) -> Option<Self> {
+        Some(value.clone())
+    }
+
+    fn to_bigint(&self) -> BigInt {
+        self.clone()
+    }
+}
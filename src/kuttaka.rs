@@ -0,0 +1,206 @@
+//! The kuttaka ("pulverizer"): linear Diophantine equations and congruences.
+//!
+//! Every function is generic over the integer type. For fixed-width types a
+//! product that would overflow makes the function return None, just as when
+//! there is no solution; BigInt never overflows.
+
+use crate::int::CycleInt;
+
+/// Every solution of a*x + b*y = c: (x0 + t*dx, y0 + t*dy) for all integers t.
+///
+/// dx >= 0, and x0 is the least non-negative x unless dx = 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinearSolution<T> {
+    pub x0: T,
+    pub y0: T,
+    pub dx: T,
+    pub dy: T,
+}
+
+impl<T: CycleInt> LinearSolution<T> {
+    /// The solution for parameter t.
+    pub fn at(&self, t: &T) -> Option<(T, T)> {
+        let x = self.x0.checked_add(&t.checked_mul(&self.dx)?)?;
+        let y = self.y0.checked_add(&t.checked_mul(&self.dy)?)?;
+        Some((x, y))
+    }
+}
+
+/// The residue class x = residue (mod modulus), with 0 <= residue < modulus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Congruence<T> {
+    pub residue: T,
+    pub modulus: T,
+}
+
+impl<T: CycleInt> Congruence<T> {
+    /// Every integer: 0 (mod 1).
+    pub fn all() -> Self {
+        Congruence { residue: T::zero(), modulus: T::one() }
+    }
+
+    pub fn contains(&self, x: &T) -> bool {
+        x.mod_floor(&self.modulus) == self.residue
+    }
+}
+
+/// (g, x, y) with g = gcd(a, b) >= 0 and a*x + b*y = g, or None if a step
+/// overflows, e.g. when g = 2^63 does not fit in i64.
+pub fn extended_gcd<T: CycleInt>(a: &T, b: &T) -> Option<(T, T, T)> {
+    // Repeated division keeps r_i = a*x_i + b*y_i for both rows.
+    let (mut r0, mut r1) = (a.clone(), b.clone());
+    let (mut x0, mut x1) = (T::one(), T::zero());
+    let (mut y0, mut y1) = (T::zero(), T::one());
+
+    while !r1.is_zero() {
+        let q = r0.checked_div(&r1)?;
+        let r2 = r0.checked_sub(&q.checked_mul(&r1)?)?;
+        let x2 = x0.checked_sub(&q.checked_mul(&x1)?)?;
+        let y2 = y0.checked_sub(&q.checked_mul(&y1)?)?;
+        (r0, r1) = (r1, r2);
+        (x0, x1) = (x1, x2);
+        (y0, y1) = (y1, y2);
+    }
+
+    if r0.is_negative() {
+        let neg = |v: &T| T::zero().checked_sub(v);
+        Some((neg(&r0)?, neg(&x0)?, neg(&y0)?))
+    } else {
+        Some((r0, x0, y0))
+    }
+}
+
+/// Inverse of `a` modulo `m` > 0, if gcd(a, m) = 1.
+pub fn mod_inverse<T: CycleInt>(a: &T, m: &T) -> Option<T> {
+    if !m.is_positive() {
+        return None;
+    }
+    let (g, x, _) = extended_gcd(&a.mod_floor(m), m)?;
+    if g.is_one() { Some(x.mod_floor(m)) } else { None }
+}
+
+/// Solves a*x + b*y = c. Returns None if gcd(a, b) does not divide c, or if
+/// a = b = 0.
+pub fn solve_linear<T: CycleInt>(a: &T, b: &T, c: &T) -> Option<LinearSolution<T>> {
+    let (g, x, y) = extended_gcd(a, b)?;
+    if g.is_zero() || !c.is_multiple_of(&g) {
+        return None;
+    }
+
+    // Scale the Bezout coefficients up to c, then step along (b/g, -a/g),
+    // turned so that dx >= 0.
+    let scale = c.clone() / g.clone();
+    let (mut x0, mut y0) = (x.checked_mul(&scale)?, y.checked_mul(&scale)?);
+    let (mut dx, mut dy) = (b.clone() / g.clone(), T::zero().checked_sub(&(a.clone() / g))?);
+    if dx.is_negative() {
+        (dx, dy) = (T::zero().checked_sub(&dx)?, T::zero().checked_sub(&dy)?);
+    }
+
+    if !dx.is_zero() {
+        let t = x0.div_floor(&dx);
+        x0 = x0.checked_sub(&t.checked_mul(&dx)?)?;
+        y0 = y0.checked_sub(&t.checked_mul(&dy)?)?;
+    }
+
+    Some(LinearSolution { x0, y0, dx, dy })
+}
+
+/// Solves a*x = b (mod m) for m > 0. The solutions form one class modulo
+/// m / gcd(a, m); None if gcd(a, m) does not divide b.
+pub fn solve_congruence<T: CycleInt>(a: &T, b: &T, m: &T) -> Option<Congruence<T>> {
+    if !m.is_positive() {
+        return None;
+    }
+
+    // a*x + m*y = b, with a and b reduced first so nothing grows past m^2.
+    let solution = solve_linear(&a.mod_floor(m), m, &b.mod_floor(m))?;
+    let modulus = solution.dx;
+    Some(Congruence { residue: solution.x0.mod_floor(&modulus), modulus })
+}
+
+/// Combines x = r1 (mod m1) and x = r2 (mod m2), whose moduli need not be
+/// coprime, into one class modulo lcm(m1, m2). None if they are incompatible.
+pub fn crt<T: CycleInt>(first: &Congruence<T>, second: &Congruence<T>) -> Option<Congruence<T>> {
+    // x = r1 + m1*t with m1*t = r2 - r1 (mod m2).
+    let diff = second.residue.checked_sub(&first.residue)?;
+    let t = solve_congruence(&first.modulus, &diff, &second.modulus)?;
+
+    let modulus = first.modulus.checked_mul(&t.modulus)?;
+    let residue = first.residue.checked_add(&first.modulus.checked_mul(&t.residue)?)?;
+    Some(Congruence { residue: residue.mod_floor(&modulus), modulus })
+}
+
+/// Solves the system a_i*x = b_i (mod m_i), given as (a_i, b_i, m_i).
+pub fn solve_system<T: CycleInt>(congruences: &[(T, T, T)]) -> Option<Congruence<T>> {
+    congruences.iter().try_fold(Congruence::all(), |acc, (a, b, m)| {
+        crt(&acc, &solve_congruence(a, b, m)?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigInt;
+
+    use super::*;
+
+    fn class(residue: i64, modulus: i64) -> Option<Congruence<i64>> {
+        Some(Congruence { residue, modulus })
+    }
+
+    #[test]
+    fn gcd_overflow() {
+        let (g, x, y) = extended_gcd(&240i64, &46).unwrap();
+        assert_eq!((g, 240 * x + 46 * y), (2, 2));
+        // gcd(-2^63, 0) = 2^63 does not fit in i64, but does in BigInt.
+        assert_eq!(extended_gcd(&i64::MIN, &0), None);
+        let (g, _, _) = extended_gcd(&BigInt::from(i64::MIN), &BigInt::from(0)).unwrap();
+        assert_eq!(g, BigInt::from(1u64 << 63));
+        assert_eq!(mod_inverse(&3i64, &7), Some(5));
+        assert_eq!(mod_inverse(&4i64, &6), None);
+    }
+
+    #[test]
+    fn linear_equations() {
+        // 3*4 + 5*(-1) = 7.
+        let solution = solve_linear(&3i64, &5, &7).unwrap();
+        assert_eq!(solution, LinearSolution { x0: 4, y0: -1, dx: 5, dy: -3 });
+        assert_eq!(solution.at(&-2), Some((-6, 5)));
+        // A negative b still gives dx >= 0.
+        assert_eq!(solve_linear(&3i64, &-5, &7), Some(LinearSolution { x0: 4, y0: 1, dx: 5, dy: 3 }));
+        assert_eq!(solve_linear(&4i64, &6, &5), None);
+        assert_eq!(solve_linear(&0i64, &0, &0), None);
+        assert_eq!(solve_linear(&i64::MIN, &1, &0), None);
+    }
+
+    #[test]
+    fn congruences_match_brute_force() {
+        for m in 1i64..20 {
+            for a in -20..20 {
+                for b in -20..20 {
+                    let expected: Vec<i64> = (0..m).filter(|x| (a * x - b) % m == 0).collect();
+                    let found = solve_congruence(&a, &b, &m);
+                    let got: Vec<i64> = (0..m).filter(|x| found.as_ref().is_some_and(|c| c.contains(x))).collect();
+                    assert_eq!(got, expected, "{a}x = {b} (mod {m})");
+                }
+            }
+        }
+        assert_eq!(solve_congruence(&6i64, &4, &10), class(4, 5));
+        assert_eq!(solve_congruence(&6i64, &3, &10), None);
+        assert_eq!(solve_congruence(&1i64, &1, &0), None);
+    }
+
+    #[test]
+    fn chinese_remainders() {
+        assert_eq!(crt(&class(2, 3).unwrap(), &class(3, 5).unwrap()), class(8, 15));
+        // Moduli with a common factor: compatible, then incompatible.
+        assert_eq!(crt(&class(1, 4).unwrap(), &class(3, 6).unwrap()), class(9, 12));
+        assert_eq!(crt(&class(0, 4).unwrap(), &class(1, 6).unwrap()), None);
+
+        assert_eq!(solve_system(&[(1i64, 2, 3), (1, 3, 5), (1, 2, 7)]), class(23, 105));
+        assert_eq!(solve_system(&[(2i64, 2, 6), (3, 1, 4)]), class(7, 12));
+        assert_eq!(solve_system::<i64>(&[]), class(0, 1));
+        // 2x = 1 (mod 4) has no solution; x = 0 (mod 4) and x = 1 (mod 6) are incompatible.
+        assert_eq!(solve_system(&[(1i64, 2, 3), (2, 1, 4)]), None);
+        assert_eq!(solve_system(&[(1i64, 0, 4), (1, 1, 6)]), None);
+    }
+}
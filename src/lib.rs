@@ -0,0 +1,40 @@
+//! Solves Pell's equation x^2 - N*y^2 = 1 using the Chakravala method.
+
+pub mod continued_fraction;
+pub mod kuttaka;
+
+mod backend;
+mod batch;
+mod compact;
+mod error;
+mod estimate;
+mod general;
+mod int;
+mod modular;
+mod options;
+mod quadratic;
+mod regulator;
+mod shortcut;
+mod solutions;
+mod solver;
+mod strategy;
+mod unit;
+
+pub use backend::{ContinuedFraction, PellBackend, Verify};
+pub use batch::{RangeEntry, RangeSolutions};
+pub use compact::CompactUnit;
+pub use error::{PartialState, PellError};
+pub use estimate::{Estimate, estimate};
+pub use general::{ClassSolutions, GeneralSolution, SolutionClass};
+pub use int::CycleInt;
+pub use modular::ModularSolution;
+pub use options::{CancelToken, SolveOptions};
+pub use quadratic::{PellTriple, QuadInt};
+pub use regulator::{Regulator, regulator, regulator_of};
+pub use shortcut::{Shortcut, ShortcutReport};
+pub use solutions::PellSolutions;
+pub use solver::{Cycle, PellEquation, Solution, Solver, Step, chakravala};
+pub use strategy::{
+    Ayyangar, MinimizeNewK, MinimizeNorm, MultiplierStrategy, NearestRoot, StrategyReport, compare_strategies,
+};
+pub use unit::{FundamentalUnit, fundamental_unit, fundamental_unit_unchecked};
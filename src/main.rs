@@ -1,134 +1,682 @@
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::Instant;
+
+use chakravala::continued_fraction::sqrt_expansion;
+use chakravala::{PellEquation, PellError, Solution, Solver, compare_strategies, estimate};
 use num_bigint::BigInt;
-use num_traits::{One, Signed, ToPrimitive, Zero};
-
-/// Solves x^2 - N*y^2 = 1 using the Chakravala method.
-/// Returns (x, y).
-fn chakravala(n: u32) -> Option<(BigInt, BigInt)> {
-    let n_big = BigInt::from(n);
-
-    // 1. Check if N is a perfect square (no solution if so)
-    let sqrt_n = n_big.sqrt();
-    if &sqrt_n * &sqrt_n == n_big {
-        println!("N={} is a perfect square. No solution exists.", n);
-        return None;
-    }
-
-    // 2. Initialisation
-    // We want a^2 - N*b^2 = k.
-    // Standard start: b = 1, a = closest integer to sqrt(N).
-    let mut a: BigInt = n_big.sqrt();
-    let mut b: BigInt = BigInt::one();
-    
-    // Adjust 'a' to be the closest integer to sqrt(N)
-    // currently a = floor(sqrt(N)). Check if ceil(sqrt(N)) is closer.
-    let root = n_big.sqrt();
-    let diff1 = (&n_big - &root * &root).abs();
-    let root_plus = &root + &BigInt::one();
-    let diff2 = (&root_plus * &root_plus - &n_big).abs();
-
-    if diff2 < diff1 {
-        a = root_plus;
-    } else {
-        a = root;
-    }
-
-    let mut k: BigInt = &a * &a - &n_big * &b * &b;
-
-    println!("Starting triple: a={}, b={}, k={}", a, b, k);
-
-    // 3. Main Loop
-    // Cycle until k = 1.
-    // If k = -1 or -2, or 2, the method guarantees convergence to 1 quickly.
-    while k != BigInt::one() {
-        // Find m such that:
-        // 1. (a + b*m) is divisible by k
-        // 2. |m^2 - N| is minimized
-        let m = find_optimal_m(&n_big, &a, &b, &k);
-
-        // Update a, b, k using Bhaskara's identity (Samasa)
-        // new_k = (m^2 - N) / k
-        // new_a = (a*m + N*b) / |k|
-        // new_b = (a + b*m) / |k|
-        
-        let abs_k = k.abs();
-        
-        let new_k = (&m * &m - &n_big) / &k;
-        let new_a = (&a * &m + &n_big * &b) / &abs_k;
-        let new_b = (&a + &b * &m) / &abs_k;
-
-        a = new_a;
-        b = new_b;
-        k = new_k;
-
-        // println!("Step: a={}, b={}, k={}", a, b, k); // Uncomment for debug
-    }
-
-    Some((a, b))
-}
-
-/// Finds 'm' such that (a + b*m) % k == 0 and |m^2 - N| is minimized.
-fn find_optimal_m(n: &BigInt, a: &BigInt, b: &BigInt, k: &BigInt) -> BigInt {
-    let abs_k = k.abs();
-    let target = n.sqrt();
-
-    let mut best_m: Option<BigInt> = None;
-    let mut min_diff: Option<BigInt> = None;
-
-    // Search range: |k| + 2 (or a reasonable cap if |k| is huge)
-    let limit = abs_k.to_u64().unwrap_or(1000).saturating_add(2);
-
-    for offset in 0..limit {
-        let o = BigInt::from(offset);
-        let candidates = if offset == 0 {
-            vec![target.clone()]
-        } else {
-            vec![&target + &o, &target - &o]
+
+const HELP: &str = "\
+Solves Pell's equation x^2 - N*y^2 = 1 and its relatives with the Chakravala method.
+
+usage: chakravala <command> [arguments] [options]
+
+commands:
+  solve [N...]       fundamental solution of x^2 - N*y^2 = 1
+  negative [N...]    fundamental solution of x^2 - N*y^2 = -1
+  general [N c...]   every class of solutions of x^2 - N*y^2 = c
+  trace [N...]       every step of the Chakravala cycle
+  range <a> <b>      solve every non-square N with a <= N < b on all threads
+  strategies [N...]  compare cycle length and sizes across multiplier strategies
+
+Arguments left out are read from stdin, separated by whitespace.
+`chakravala N` is short for `chakravala solve N`.
+
+options:
+  --format F         text (default), json, jsonl or csv
+  --max-digits D     solve only: refuse when x would have more than D digits
+  --warn-digits D    solve only: warn when x will have more than D digits
+  --threads T        range only: number of threads (default: all available)
+  -h, --help         print this help
+
+exit status:
+  0  success
+  1  a solve failed, e.g. N is a perfect square
+  2  invalid command line or input
+  3  refused by --max-digits
+  4  the equation has no solution";
+
+/// Exit status of a run. The discriminants are the exit codes; `severity`
+/// decides which outcome wins when a run has several.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Success = 0,
+    NoSolution = 4,
+    TooLarge = 3,
+    Failed = 1,
+    Usage = 2,
+}
+
+impl Status {
+    fn severity(self) -> u8 {
+        match self {
+            Status::Success => 0,
+            Status::NoSolution => 1,
+            Status::TooLarge => 2,
+            Status::Failed => 3,
+            Status::Usage => 4,
+        }
+    }
+
+    /// The worse of the two outcomes.
+    fn worst(self, other: Status) -> Status {
+        if other.severity() > self.severity() { other } else { self }
+    }
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Solve,
+    Negative,
+    General,
+    Trace,
+    Range,
+    Strategies,
+}
+
+impl Command {
+    fn parse(name: &str) -> Option<Command> {
+        match name {
+            "solve" => Some(Command::Solve),
+            "negative" => Some(Command::Negative),
+            "general" => Some(Command::General),
+            "trace" => Some(Command::Trace),
+            "range" => Some(Command::Range),
+            "strategies" => Some(Command::Strategies),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+    Jsonl,
+    Csv,
+}
+
+impl Format {
+    fn parse(name: &str) -> Option<Format> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "jsonl" => Some(Format::Jsonl),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+struct Options {
+    command: Command,
+    args: Vec<String>,
+    format: Format,
+    max_digits: Option<u64>,
+    warn_digits: Option<u64>,
+    threads: Option<usize>,
+}
+
+/// Parses the command line. Ok(None) means help was requested.
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut args = args.peekable();
+    let command = match args.peek().map(String::as_str) {
+        None => return Err("missing command".to_string()),
+        Some("-h" | "--help") => return Ok(None),
+        Some(name) => match Command::parse(name) {
+            Some(command) => {
+                args.next();
+                command
+            }
+            // A bare N solves it, as before there were subcommands.
+            None if name.parse::<BigInt>().is_ok() => Command::Solve,
+            None => return Err(format!("unknown command '{}'", name)),
+        },
+    };
+
+    let mut options = Options {
+        command,
+        args: Vec::new(),
+        format: Format::Text,
+        max_digits: None,
+        warn_digits: None,
+        threads: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--format" => {
+                let name = value("--format")?;
+                options.format = Format::parse(&name).ok_or_else(|| format!("unknown format '{}'", name))?;
+            }
+            "--max-digits" => options.max_digits = Some(parse_number(&value("--max-digits")?)?),
+            "--warn-digits" => options.warn_digits = Some(parse_number(&value("--warn-digits")?)?),
+            "--threads" => options.threads = Some(parse_number(&value("--threads")?)?),
+            flag if flag.starts_with("--") => return Err(format!("unknown option '{}'", flag)),
+            _ => options.args.push(arg),
+        }
+    }
+
+    // Only solve predicts the size of x before solving.
+    if options.command != Command::Solve && (options.max_digits.is_some() || options.warn_digits.is_some()) {
+        return Err("--max-digits and --warn-digits only apply to solve".to_string());
+    }
+    if options.command != Command::Range && options.threads.is_some() {
+        return Err("--threads only applies to range".to_string());
+    }
+    Ok(Some(options))
+}
+
+fn parse_number<T: FromStr>(token: &str) -> Result<T, String> {
+    token.parse().map_err(|_| format!("invalid number '{}'", token))
+}
+
+/// The positional arguments as numbers, or the whitespace-separated tokens
+/// of stdin if there are none. Empty stdin is an error.
+fn inputs<T: FromStr>(args: &[String]) -> Result<Vec<T>, String> {
+    if !args.is_empty() {
+        return args.iter().map(|arg| parse_number(arg)).collect();
+    }
+    let stdin = std::io::read_to_string(std::io::stdin()).map_err(|e| format!("cannot read stdin: {}", e))?;
+    let tokens: Vec<&str> = stdin.split_whitespace().collect();
+    if tokens.is_empty() {
+        return Err("no input: give numbers as arguments or on stdin".to_string());
+    }
+    tokens.into_iter().map(parse_number).collect()
+}
+
+/// One cell of a structured output row.
+enum Value {
+    Text(String),
+    Number(usize),
+    Float(f64),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    fn json(&self) -> String {
+        match self {
+            Value::Text(s) => quote(s),
+            Value::Number(v) => v.to_string(),
+            Value::Float(v) => format!("{:.3}", v),
+            Value::Bool(v) => v.to_string(),
+            Value::Null => "null".to_string(),
+        }
+    }
+
+    fn csv(&self) -> String {
+        match self {
+            Value::Text(s) => csv_field(s),
+            Value::Number(v) => v.to_string(),
+            Value::Float(v) => format!("{:.3}", v),
+            Value::Bool(v) => v.to_string(),
+            Value::Null => String::new(),
+        }
+    }
+}
+
+// Integers of any size are written as strings so JSON readers keep every digit.
+impl From<&BigInt> for Value {
+    fn from(value: &BigInt) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+/// Writes rows with a fixed set of columns as JSON, JSON Lines or CSV.
+struct Output {
+    format: Format,
+    columns: &'static [&'static str],
+    rows: usize,
+}
+
+impl Output {
+    fn begin(format: Format, columns: &'static [&'static str]) -> Self {
+        match format {
+            Format::Json => println!("["),
+            Format::Csv => println!("{}", columns.join(",")),
+            Format::Text | Format::Jsonl => {}
+        }
+        Output { format, columns, rows: 0 }
+    }
+
+    /// Prints one row. `values` follow `columns`; missing trailing values are null.
+    fn row(&mut self, mut values: Vec<Value>) {
+        values.resize_with(self.columns.len(), || Value::Null);
+        self.rows += 1;
+
+        match self.format {
+            Format::Json | Format::Jsonl => {
+                let fields: Vec<String> = self
+                    .columns
+                    .iter()
+                    .zip(&values)
+                    .map(|(name, value)| format!("\"{}\":{}", name, value.json()))
+                    .collect();
+                let separator = if self.format == Format::Json && self.rows > 1 { "," } else { "" };
+                println!("{}{{{}}}", separator, fields.join(","));
+            }
+            Format::Csv => {
+                let cells: Vec<String> = values.iter().map(Value::csv).collect();
+                println!("{}", cells.join(","));
+            }
+            Format::Text => unreachable!("text output is printed by each command"),
+        }
+    }
+
+    fn finish(self) {
+        if self.format == Format::Json {
+            println!("]");
+        }
+    }
+}
+
+/// A JSON string literal.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) { format!("\"{}\"", s.replace('"', "\"\"")) } else { s.to_string() }
+}
+
+/// `leading` padded with nulls, then the error in the last column.
+fn error_row(mut leading: Vec<Value>, columns: &[&str], error: &PellError) -> Vec<Value> {
+    leading.resize_with(columns.len() - 1, || Value::Null);
+    leading.push(Value::Text(error.to_string()));
+    leading
+}
+
+const SOLVE_COLUMNS: &[&str] = &[
+    "n", "x", "y", "steps", "shortcut", "saved", "period", "digits_x", "digits_y", "verified", "error",
+];
+
+/// Row for x^2 - N*y^2 = 1: the solution, the period length of sqrt(N),
+/// the digit counts and whether the solution checks out.
+fn solution_row(n: &BigInt, result: &Result<Solution, PellError>) -> Vec<Value> {
+    let s = match result {
+        Ok(s) => s,
+        Err(e) => return error_row(vec![n.into()], SOLVE_COLUMNS, e),
+    };
+
+    let (x, y) = (s.x.to_string(), s.y.to_string());
+    let (digits_x, digits_y) = (x.len(), y.len());
+    let period = sqrt_expansion(n.clone()).map_or(Value::Null, |e| Value::Number(e.period_len()));
+    let verified = PellEquation::new(n.clone()).is_solution(&s.x, &s.y);
+    vec![
+        n.into(),
+        Value::Text(x),
+        Value::Text(y),
+        Value::Number(s.steps),
+        s.shortcut.map_or(Value::Null, |r| Value::Text(r.shortcut.to_string())),
+        s.shortcut.and_then(|r| r.saved).map_or(Value::Null, Value::Number),
+        period,
+        Value::Number(digits_x),
+        Value::Number(digits_y),
+        Value::Bool(verified),
+    ]
+}
+
+/// Predicts the size of x1 and applies --max-digits and --warn-digits.
+/// Returns false if the solve should be skipped.
+fn check_size(n: &BigInt, options: &Options) -> bool {
+    if options.max_digits.is_none() && options.warn_digits.is_none() {
+        return true;
+    }
+    // An error here is reported again by the solve itself.
+    let Ok(est) = estimate(n.clone()) else { return true };
+
+    if let Some(max) = options.max_digits
+        && est.digits_x > max
+    {
+        eprintln!(
+            "N={}: x would have about {} digits (limit {}), refusing to solve",
+            n, est.digits_x, max
+        );
+        return false;
+    }
+    if options.warn_digits.is_some_and(|warn| est.digits_x > warn) {
+        eprintln!(
+            "warning: N={}: x will have about {} digits after {} steps",
+            n, est.digits_x, est.steps
+        );
+    }
+    true
+}
+
+fn solve(options: &Options) -> Result<Status, String> {
+    let values: Vec<BigInt> = inputs(&options.args)?;
+    let mut output = Output::begin(options.format, SOLVE_COLUMNS);
+    let mut status = Status::Success;
+
+    for n in values {
+        if !check_size(&n, options) {
+            status = status.worst(Status::TooLarge);
+            continue;
+        }
+
+        let eq = PellEquation::new(n.clone());
+        let result = Solver::new().solve(&eq);
+        if result.is_err() {
+            status = status.worst(Status::Failed);
+        }
+
+        if options.format != Format::Text {
+            output.row(solution_row(&n, &result));
+            continue;
+        }
+
+        println!("Solving Pell's equation x^2 - {}y^2 = 1...", n);
+        match result {
+            Ok(solution) => {
+                let (x, y) = (&solution.x, &solution.y);
+                println!("--- Solution Found ---");
+                println!("x = {}", x);
+                println!("y = {}", y);
+
+                // Verify
+                let lhs = x * x - &n * y * y;
+                println!("Check: x^2 - {}y^2 = {}", n, lhs);
+                if let Some(r) = solution.shortcut {
+                    match r.saved {
+                        Some(saved) => {
+                            println!("Shortcut at {} after step {}, saving {} steps", r.shortcut, r.at_step, saved)
+                        }
+                        None => println!("Shortcut at {} after step {}", r.shortcut, r.at_step),
+                    }
+                }
+            }
+            Err(e) => eprintln!("Could not solve: {}", e),
+        }
+    }
+
+    output.finish();
+    Ok(status)
+}
+
+const RANGE_COLUMNS: &[&str] = &[
+    "n", "x", "y", "steps", "shortcut", "saved", "period", "digits_x", "digits_y", "verified", "elapsed_ms", "error",
+];
+
+/// Solves every non-square N in start..end, one record per N.
+fn range(options: &Options) -> Result<Status, String> {
+    let [start, end] = inputs::<u64>(&options.args)?[..] else {
+        return Err("range needs a start and an end".to_string());
+    };
+
+    let mut solver = Solver::new();
+    if let Some(threads) = options.threads {
+        solver = solver.threads(threads);
+    }
+
+    let mut output = Output::begin(options.format, RANGE_COLUMNS);
+    let started = Instant::now();
+    let (mut solved, mut failed) = (0usize, 0usize);
+
+    for entry in solver.solve_range(start, end) {
+        match &entry.result {
+            Ok(_) => solved += 1,
+            Err(_) => failed += 1,
+        }
+
+        match (options.format, &entry.result) {
+            (Format::Text, Ok(solution)) => {
+                println!("N={} x={} y={} ({:?})", entry.n, solution.x, solution.y, entry.elapsed)
+            }
+            (Format::Text, Err(e)) => eprintln!("N={}: could not solve: {}", entry.n, e),
+            _ => {
+                // The solve row with the time spent on N just before the error column.
+                let mut row = solution_row(&BigInt::from(entry.n), &entry.result);
+                row.resize_with(SOLVE_COLUMNS.len(), || Value::Null);
+                row.insert(SOLVE_COLUMNS.len() - 1, Value::Float(entry.elapsed.as_secs_f64() * 1000.0));
+                output.row(row);
+            }
+        }
+    }
+
+    output.finish();
+    eprintln!("solved {} values of N in {:?}, {} failed", solved, started.elapsed(), failed);
+    Ok(if failed == 0 { Status::Success } else { Status::Failed })
+}
+
+const NEGATIVE_COLUMNS: &[&str] = &["n", "x", "y", "verified", "error"];
+
+fn negative(options: &Options) -> Result<Status, String> {
+    let values: Vec<BigInt> = inputs(&options.args)?;
+    let mut output = Output::begin(options.format, NEGATIVE_COLUMNS);
+    let mut status = Status::Success;
+
+    for n in values {
+        let eq = PellEquation::new(n.clone());
+        let result = Solver::new().solve_negative(&eq);
+        status = status.worst(match result {
+            Ok(Some(_)) => Status::Success,
+            Ok(None) => Status::NoSolution,
+            Err(_) => Status::Failed,
+        });
+
+        match (options.format, result) {
+            (Format::Text, Ok(Some((x, y)))) => {
+                println!("x^2 - {}y^2 = -1", n);
+                println!("x = {}", x);
+                println!("y = {}", y);
+            }
+            (Format::Text, Ok(None)) => println!("x^2 - {}y^2 = -1 has no solution", n),
+            (Format::Text, Err(e)) => eprintln!("Could not solve: {}", e),
+            (_, Ok(Some((x, y)))) => {
+                let verified = eq.is_negative_solution(&x, &y);
+                output.row(vec![(&n).into(), (&x).into(), (&y).into(), Value::Bool(verified)]);
+            }
+            (_, Ok(None)) => output.row(vec![(&n).into()]),
+            (_, Err(e)) => output.row(error_row(vec![(&n).into()], NEGATIVE_COLUMNS, &e)),
+        }
+    }
+
+    output.finish();
+    Ok(status)
+}
+
+const GENERAL_COLUMNS: &[&str] = &["n", "c", "x", "y", "error"];
+
+fn general(options: &Options) -> Result<Status, String> {
+    let values: Vec<BigInt> = inputs(&options.args)?;
+    if values.is_empty() || !values.len().is_multiple_of(2) {
+        return Err("general needs pairs of N and c".to_string());
+    }
+
+    let mut output = Output::begin(options.format, GENERAL_COLUMNS);
+    let mut status = Status::Success;
+
+    for pair in values.chunks(2) {
+        let (n, c) = (&pair[0], &pair[1]);
+        let eq = PellEquation::new(n.clone());
+        let classes = match Solver::new().solve_general(&eq, c.clone()) {
+            Ok(general) => general.classes,
+            Err(e) => {
+                status = status.worst(Status::Failed);
+                match options.format {
+                    Format::Text => eprintln!("Could not solve: {}", e),
+                    _ => output.row(error_row(vec![n.into(), c.into()], GENERAL_COLUMNS, &e)),
+                }
+                continue;
+            }
         };
+        if classes.is_empty() {
+            status = status.worst(Status::NoSolution);
+        }
+
+        if options.format == Format::Text {
+            if classes.is_empty() {
+                println!("x^2 - {}y^2 = {} has no solution", n, c);
+            } else {
+                println!("x^2 - {}y^2 = {}: {} classes", n, c, classes.len());
+            }
+            for class in &classes {
+                println!("x = {}, y = {}", class.x, class.y);
+            }
+        } else if classes.is_empty() {
+            output.row(vec![n.into(), c.into()]);
+        } else {
+            for class in &classes {
+                output.row(vec![n.into(), c.into(), (&class.x).into(), (&class.y).into()]);
+            }
+        }
+    }
 
-        for candidate in candidates {
-            if candidate <= BigInt::zero() { continue; }
+    output.finish();
+    Ok(status)
+}
+
+const TRACE_COLUMNS: &[&str] = &["n", "step", "a", "b", "k", "m", "error"];
 
-            // Check divisibility: (a + b*m) % |k| == 0
-            let sum = a + b * &candidate;
-            if &sum % &abs_k == BigInt::zero() {
-                let diff = (&candidate * &candidate - n).abs();
+fn trace(options: &Options) -> Result<Status, String> {
+    let values: Vec<BigInt> = inputs(&options.args)?;
+    let text = options.format == Format::Text;
+    let mut output = Output::begin(options.format, TRACE_COLUMNS);
+    let mut status = Status::Success;
 
-                if best_m.is_none() || min_diff.as_ref().map_or(true, |d| diff < *d) {
-                    min_diff = Some(diff);
-                    best_m = Some(candidate);
+    for n in values {
+        let eq = PellEquation::new(n.clone());
+        let cycle = match Solver::new().trace(&eq) {
+            Ok(cycle) => cycle,
+            Err(e) => {
+                status = status.worst(Status::Failed);
+                if text {
+                    eprintln!("Could not solve: {}", e);
                 } else {
-                    // If we've already found a valid m and differences are increasing,
-                    // it's reasonable to break early.
-                    if offset > 5 { break; }
+                    output.row(error_row(vec![(&n).into()], TRACE_COLUMNS, &e));
                 }
+                continue;
             }
+        };
+
+        let (a, b, k) = cycle.triple();
+        if text {
+            println!("Chakravala cycle for N={}", n);
+            println!("step 0: (a, b, k) = ({}, {}, {})", a, b, k);
+        } else {
+            output.row(vec![(&n).into(), Value::Number(0), a.into(), b.into(), k.into()]);
         }
 
-        if best_m.is_some() && offset > abs_k.to_u64().unwrap_or(0).min(10) {
-            // found a candidate and searched reasonably far: stop
-            break;
+        for (i, step) in (1..).zip(cycle) {
+            match step {
+                Ok(s) if text => {
+                    println!("step {}: m = {}, (a, b, k) = ({}, {}, {})", i, s.m, s.new_a, s.new_b, s.new_k)
+                }
+                Ok(s) => output.row(vec![
+                    (&n).into(),
+                    Value::Number(i),
+                    (&s.new_a).into(),
+                    (&s.new_b).into(),
+                    (&s.new_k).into(),
+                    (&s.m).into(),
+                ]),
+                Err(e) => {
+                    status = status.worst(Status::Failed);
+                    if text {
+                        eprintln!("Could not solve: {}", e);
+                    } else {
+                        output.row(error_row(vec![(&n).into(), Value::Number(i)], TRACE_COLUMNS, &e));
+                    }
+                }
+            }
         }
     }
 
-    best_m.expect("Failed to find valid m (should not happen in Chakravala)")
+    output.finish();
+    Ok(status)
 }
 
-fn main() {
-    // Example: Solve x^2 - 61y^2 = 1
-    // 61 is a famous test case (solutions are large).
-    let n = 61;
-    println!("Solving Pell's equation x^2 - {}y^2 = 1...", n);
-    
-    match chakravala(n) {
-        Some((x, y)) => {
-            println!("--- Solution Found ---");
-            println!("x = {}", x);
-            println!("y = {}", y);
-            
-            // Verify
-            let lhs = &x * &x - BigInt::from(n) * &y * &y;
-            println!("Check: x^2 - {}y^2 = {}", n, lhs);
+const STRATEGY_COLUMNS: &[&str] = &["n", "strategy", "steps", "max_k", "mean_k", "max_m", "error"];
+
+fn strategies(options: &Options) -> Result<Status, String> {
+    let values: Vec<BigInt> = inputs(&options.args)?;
+    let mut output = Output::begin(options.format, STRATEGY_COLUMNS);
+    let mut status = Status::Success;
+
+    for n in values {
+        let reports = match compare_strategies(&PellEquation::new(n.clone())) {
+            Ok(reports) => reports,
+            Err(e) => {
+                status = status.worst(Status::Failed);
+                match options.format {
+                    Format::Text => eprintln!("Could not solve: {}", e),
+                    _ => output.row(error_row(vec![(&n).into()], STRATEGY_COLUMNS, &e)),
+                }
+                continue;
+            }
+        };
+
+        if options.format == Format::Text {
+            println!("N={}", n);
+            println!("{:<16}{:>10}{:>12}{:>12}{:>12}", "strategy", "steps", "max |k|", "mean |k|", "max m");
+        }
+        for r in reports {
+            if options.format == Format::Text {
+                println!("{:<16}{:>10}{:>12}{:>12.3}{:>12}", r.strategy, r.steps, r.max_k, r.mean_k, r.max_m);
+            } else {
+                output.row(vec![
+                    (&n).into(),
+                    Value::Text(r.strategy.to_string()),
+                    Value::Number(r.steps),
+                    (&r.max_k).into(),
+                    Value::Float(r.mean_k),
+                    (&r.max_m).into(),
+                ]);
+            }
         }
-        None => println!("Could not solve."),
     }
-}
\ No newline at end of file
+
+    output.finish();
+    Ok(status)
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", HELP);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("error: {}\nrun 'chakravala --help' for usage", message);
+            return Status::Usage.into();
+        }
+    };
+
+    let result = match options.command {
+        Command::Solve => solve(&options),
+        Command::Negative => negative(&options),
+        Command::General => general(&options),
+        Command::Trace => trace(&options),
+        Command::Range => range(&options),
+        Command::Strategies => strategies(&options),
+    };
+
+    match result {
+        Ok(status) => status.into(),
+        Err(message) => {
+            eprintln!("error: {}", message);
+            Status::Usage.into()
+        }
+    }
+}
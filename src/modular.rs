@@ -0,0 +1,157 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, Zero};
+
+use crate::continued_fraction::SqrtTerms;
+use crate::error::{PartialState, PellError};
+use crate::solver::{PellEquation, Solver};
+use crate::strategy::MultiplierStrategy;
+
+/// The fundamental solution of x^2 - N*y^2 = 1 reduced modulo m.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModularSolution {
+    n: BigInt,
+    modulus: BigInt,
+    /// x1 mod m and y1 mod m.
+    pub x: BigInt,
+    pub y: BigInt,
+}
+
+impl ModularSolution {
+    pub fn modulus(&self) -> &BigInt {
+        &self.modulus
+    }
+
+    /// (x_k mod m, y_k mod m), where x_k + y_k*sqrt(N) = (x1 + y1*sqrt(N))^k,
+    /// by binary exponentiation with every product reduced modulo m.
+    pub fn nth(&self, mut k: u64) -> (BigInt, BigInt) {
+        let mut result = (BigInt::one().mod_floor(&self.modulus), BigInt::zero());
+        let mut square = (self.x.clone(), self.y.clone());
+
+        while k > 0 {
+            if k & 1 == 1 {
+                result = self.mul(&result, &square);
+            }
+            k >>= 1;
+            if k > 0 {
+                square = self.mul(&square, &square);
+            }
+        }
+        result
+    }
+
+    fn mul(&self, (x1, y1): &(BigInt, BigInt), (x2, y2): &(BigInt, BigInt)) -> (BigInt, BigInt) {
+        (
+            (x1 * x2 + &self.n * y1 * y2).mod_floor(&self.modulus),
+            (x1 * y2 + y1 * x2).mod_floor(&self.modulus),
+        )
+    }
+}
+
+impl<S: MultiplierStrategy> Solver<S> {
+    /// Returns x1 and y1 modulo `modulus` without ever holding them in full.
+    ///
+    /// The convergents p_i / q_i of sqrt(N) follow p_i = a_i*p_{i-1} + p_{i-2},
+    /// which needs no division, so they are reduced modulo m throughout. Over a
+    /// period of length l, p_{l-1} + q_{l-1}*sqrt(N) is the fundamental solution,
+    /// or for odd l the negative one, which is squared. The options count
+    /// partial quotients after a0 as steps.
+    pub fn solve_mod(&self, eq: &PellEquation, modulus: impl Into<BigInt>) -> Result<ModularSolution, PellError> {
+        let modulus = modulus.into();
+        if !modulus.is_positive() {
+            return Err(PellError::InvalidModulus { modulus });
+        }
+        let root = eq.checked_root()?;
+        let n = eq.n();
+        let end = &root * 2u32;
+
+        // (p_{i-1}, p_i) and (q_{i-1}, q_i), starting from p_{-1} = 1, q_{-1} = 0.
+        let (mut p, mut q) = ((BigInt::zero(), BigInt::one()), (BigInt::one(), BigInt::zero()));
+        // Partial quotients so far, to rebuild the exact convergent if the options stop the run.
+        let mut terms: Vec<BigInt> = Vec::new();
+
+        for a in SqrtTerms::new(n, &root) {
+            if a == end {
+                break;
+            }
+            if let Some(steps) = terms.len().checked_sub(1)
+                && let Some(stop) = self.options.stop(steps)
+            {
+                let (a, b) = convergent(&terms);
+                let k = &a * &a - n * &b * &b;
+                return Err(stop.error(PartialState { a, b, k, steps }));
+            }
+
+            p = (p.1.clone(), (&a * &p.1 + &p.0).mod_floor(&modulus));
+            q = (q.1.clone(), (&a * &q.1 + &q.0).mod_floor(&modulus));
+            terms.push(a);
+        }
+
+        let (mut x, mut y) = (p.1, q.1);
+        // a0 plus the period without its closing 2*a0: as many terms as the period is long.
+        if !terms.len().is_multiple_of(2) {
+            (x, y) = ((&x * &x + n * &y * &y).mod_floor(&modulus), (&x * &y * 2u32).mod_floor(&modulus));
+        }
+
+        Ok(ModularSolution { n: n.clone(), modulus, x, y })
+    }
+}
+
+/// The exact convergent p / q of the partial quotients `terms`, multiplying
+/// the matrices [[a, 1], [1, 0]] by binary splitting.
+fn convergent(terms: &[BigInt]) -> (BigInt, BigInt) {
+    fn product(terms: &[BigInt]) -> [BigInt; 4] {
+        match terms {
+            [] => [BigInt::one(), BigInt::zero(), BigInt::zero(), BigInt::one()],
+            [a] => [a.clone(), BigInt::one(), BigInt::one(), BigInt::zero()],
+            _ => {
+                let (left, right) = terms.split_at(terms.len() / 2);
+                let [a, b, c, d] = product(left);
+                let [e, f, g, h] = product(right);
+                [&a * &e + &b * &g, &a * &f + &b * &h, &c * &e + &d * &g, &c * &f + &d * &h]
+            }
+        }
+    }
+
+    let [p, _, q, _] = product(terms);
+    (p, q)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_full_solution() {
+        // Small moduli share primes with the k of most cycles; 10^9 + 7 is prime.
+        let moduli = [1u64, 2, 3, 4, 10, 12, 1024, 30030, 1_000_000_007];
+        let solver = Solver::new();
+        for n in 2u32..300 {
+            let eq = PellEquation::new(n);
+            if eq.checked_root().is_err() {
+                continue;
+            }
+            let solution = solver.solve(&eq).unwrap();
+            let mut powers = solver.solutions(&eq).unwrap();
+            let third = powers.nth(2).unwrap();
+            for &m in &moduli {
+                let m = BigInt::from(m);
+                let reduced = solver.solve_mod(&eq, m.clone()).unwrap();
+                let expected = (solution.x.mod_floor(&m), solution.y.mod_floor(&m));
+                assert_eq!((&reduced.x, &reduced.y), (&expected.0, &expected.1), "N = {n}, m = {m}");
+                assert_eq!(reduced.nth(3), (third.0.mod_floor(&m), third.1.mod_floor(&m)), "N = {n}, m = {m}");
+            }
+        }
+    }
+
+    #[test]
+    fn stops_with_the_exact_convergent() {
+        // sqrt(61) = [7; 1, 4, 3, 1, 2, ...]: after two steps the convergent is [7; 1, 4] = 39/5.
+        let eq = PellEquation::new(61);
+        let err = Solver::new().max_steps(2).solve_mod(&eq, 10).unwrap_err();
+        let PellError::IterationLimitExceeded { state } = err else { panic!("{err:?}") };
+        assert_eq!((state.a, state.b, state.k, state.steps), (39.into(), 5.into(), (-4).into(), 2));
+        assert_eq!(Solver::new().solve_mod(&eq, 0), Err(PellError::InvalidModulus { modulus: 0.into() }));
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::error::{PartialState, PellError};
+
+/// Shared flag that asks a running solve to stop. Clones share the flag, so
+/// one can be handed to the solver and another kept to cancel it.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    /// Stops every solve holding this token at its next step.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Bounds on a single run of the cycle, checked before every step. A run that
+/// hits one fails with the triple it had reached.
+#[derive(Debug, Clone, Default)]
+pub struct SolveOptions {
+    max_steps: Option<usize>,
+    deadline: Option<Instant>,
+    cancel: Option<CancelToken>,
+}
+
+impl SolveOptions {
+    /// No step limit, no deadline and no way to cancel.
+    pub fn new() -> Self {
+        SolveOptions::default()
+    }
+
+    /// Gives up with `IterationLimitExceeded` after this many steps.
+    pub fn max_steps(mut self, steps: usize) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// Gives up with `DeadlineExceeded` once `deadline` has passed.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the deadline to `budget` from now.
+    pub fn timeout(self, budget: Duration) -> Self {
+        self.deadline(Instant::now() + budget)
+    }
+
+    /// Gives up with `Cancelled` once `token` is cancelled.
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Why a run that has taken `steps` steps must stop, if it must.
+    pub(crate) fn stop(&self, steps: usize) -> Option<Stop> {
+        if self.max_steps.is_some_and(|max| steps >= max) {
+            Some(Stop::Steps)
+        } else {
+            self.interrupted()
+        }
+    }
+
+    /// Whether the deadline or the cancel token stops work that does not
+    /// count as steps of the run.
+    pub(crate) fn interrupted(&self) -> Option<Stop> {
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            Some(Stop::Cancelled)
+        } else if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            Some(Stop::Deadline)
+        } else {
+            None
+        }
+    }
+}
+
+/// Which of the `SolveOptions` bounds stopped a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Stop {
+    Steps,
+    Deadline,
+    Cancelled,
+}
+
+impl Stop {
+    pub(crate) fn error(self, state: PartialState) -> PellError {
+        match self {
+            Stop::Steps => PellError::IterationLimitExceeded { state },
+            Stop::Deadline => PellError::DeadlineExceeded { state },
+            Stop::Cancelled => PellError::Cancelled { state },
+        }
+    }
+}
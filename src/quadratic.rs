@@ -0,0 +1,185 @@
+use std::fmt;
+use std::ops::{Div, Mul, Neg};
+
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+
+/// The element a + b*sqrt(N) of Z[sqrt(N)].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QuadInt {
+    pub a: BigInt,
+    pub b: BigInt,
+    n: BigInt,
+}
+
+impl QuadInt {
+    pub fn new(a: impl Into<BigInt>, b: impl Into<BigInt>, n: impl Into<BigInt>) -> Self {
+        QuadInt { a: a.into(), b: b.into(), n: n.into() }
+    }
+
+    /// The multiplicative identity 1 + 0*sqrt(N).
+    pub fn one(n: impl Into<BigInt>) -> Self {
+        QuadInt::new(BigInt::one(), BigInt::zero(), n)
+    }
+
+    pub fn n(&self) -> &BigInt {
+        &self.n
+    }
+
+    /// a - b*sqrt(N).
+    pub fn conjugate(&self) -> Self {
+        QuadInt { a: self.a.clone(), b: -&self.b, n: self.n.clone() }
+    }
+
+    /// a^2 - N*b^2, the product with the conjugate.
+    pub fn norm(&self) -> BigInt {
+        &self.a * &self.a - &self.n * &self.b * &self.b
+    }
+
+    /// self^e by repeated squaring.
+    pub fn pow(&self, mut e: u64) -> Self {
+        let mut result = QuadInt::one(self.n.clone());
+        let mut square = self.clone();
+
+        while e > 0 {
+            if e & 1 == 1 {
+                result = &result * &square;
+            }
+            e >>= 1;
+            if e > 0 {
+                square = &square * &square;
+            }
+        }
+        result
+    }
+}
+
+/// Brahmagupta's identity: (a + b*sqrt(N)) * (c + d*sqrt(N)) = (ac + Nbd) + (ad + bc)*sqrt(N).
+impl Mul for &QuadInt {
+    type Output = QuadInt;
+
+    fn mul(self, rhs: &QuadInt) -> QuadInt {
+        assert_eq!(self.n, rhs.n, "elements of different rings");
+        QuadInt {
+            a: &self.a * &rhs.a + &self.n * &self.b * &rhs.b,
+            b: &self.a * &rhs.b + &self.b * &rhs.a,
+            n: self.n.clone(),
+        }
+    }
+}
+
+impl Mul for QuadInt {
+    type Output = QuadInt;
+
+    fn mul(self, rhs: QuadInt) -> QuadInt {
+        &self * &rhs
+    }
+}
+
+/// Divides both parts by d, truncating. Exact when d divides a and b.
+impl Div<&BigInt> for &QuadInt {
+    type Output = QuadInt;
+
+    fn div(self, d: &BigInt) -> QuadInt {
+        QuadInt { a: &self.a / d, b: &self.b / d, n: self.n.clone() }
+    }
+}
+
+impl Div<&BigInt> for QuadInt {
+    type Output = QuadInt;
+
+    fn div(self, d: &BigInt) -> QuadInt {
+        &self / d
+    }
+}
+
+impl Neg for QuadInt {
+    type Output = QuadInt;
+
+    fn neg(self) -> QuadInt {
+        QuadInt { a: -self.a, b: -self.b, n: self.n }
+    }
+}
+
+impl fmt::Display for QuadInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} + {}*sqrt({})", self.a, self.b, self.n)
+    }
+}
+
+/// A triple (a, b, k) with a^2 - N*b^2 = k.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PellTriple {
+    pub a: BigInt,
+    pub b: BigInt,
+    pub k: BigInt,
+    n: BigInt,
+}
+
+impl PellTriple {
+    /// The triple of a + b*sqrt(N), with k = a^2 - N*b^2.
+    pub fn new(a: impl Into<BigInt>, b: impl Into<BigInt>, n: impl Into<BigInt>) -> Self {
+        QuadInt::new(a, b, n).into()
+    }
+
+    pub fn n(&self) -> &BigInt {
+        &self.n
+    }
+
+    /// a + b*sqrt(N).
+    pub fn value(&self) -> QuadInt {
+        QuadInt::new(self.a.clone(), self.b.clone(), self.n.clone())
+    }
+}
+
+impl From<QuadInt> for PellTriple {
+    fn from(value: QuadInt) -> Self {
+        let k = value.norm();
+        PellTriple { a: value.a, b: value.b, k, n: value.n }
+    }
+}
+
+/// Composition (samasa): (a, b, k) * (c, d, l) = (ac + Nbd, ad + bc, kl).
+impl Mul for &PellTriple {
+    type Output = PellTriple;
+
+    fn mul(self, rhs: &PellTriple) -> PellTriple {
+        assert_eq!(self.n, rhs.n, "triples for different N");
+        PellTriple {
+            a: &self.a * &rhs.a + &self.n * &self.b * &rhs.b,
+            b: &self.a * &rhs.b + &self.b * &rhs.a,
+            k: &self.k * &rhs.k,
+            n: self.n.clone(),
+        }
+    }
+}
+
+impl Mul for PellTriple {
+    type Output = PellTriple;
+
+    fn mul(self, rhs: PellTriple) -> PellTriple {
+        &self * &rhs
+    }
+}
+
+/// (a / d, b / d, k / d^2), truncating. Exact when d divides a and b.
+impl Div<&BigInt> for &PellTriple {
+    type Output = PellTriple;
+
+    fn div(self, d: &BigInt) -> PellTriple {
+        PellTriple {
+            a: &self.a / d,
+            b: &self.b / d,
+            k: &self.k / (d * d),
+            n: self.n.clone(),
+        }
+    }
+}
+
+impl Div<&BigInt> for PellTriple {
+    type Output = PellTriple;
+
+    fn div(self, d: &BigInt) -> PellTriple {
+        &self / d
+    }
+}
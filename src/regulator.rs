@@ -0,0 +1,109 @@
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+
+use crate::error::PellError;
+use crate::solver::{PellEquation, Solver};
+use crate::strategy::MultiplierStrategy;
+
+/// The regulator log(x1 + y1*sqrt(N)), truncated to a fixed number of decimal digits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regulator {
+    /// The regulator times 10^digits, rounded down.
+    pub scaled: BigInt,
+    pub digits: u32,
+}
+
+impl fmt::Display for Regulator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = self.scaled.to_string();
+        let digits = self.digits as usize;
+        if digits == 0 {
+            return write!(f, "{}", text);
+        }
+
+        let text = format!("{:0>width$}", text, width = digits + 1);
+        let (int, frac) = text.split_at(text.len() - digits);
+        write!(f, "{}.{}", int, frac)
+    }
+}
+
+/// Solves `n` and returns its regulator to `digits` decimal places.
+pub fn regulator(n: impl Into<BigInt>, digits: u32) -> Result<Regulator, PellError> {
+    Solver::new().regulator(&PellEquation::new(n), digits)
+}
+
+impl<S: MultiplierStrategy> Solver<S> {
+    /// Like `regulator`, solving x^2 - N*y^2 = 1 with this solver.
+    pub fn regulator(&self, eq: &PellEquation, digits: u32) -> Result<Regulator, PellError> {
+        let solution = self.solve(eq)?;
+        Ok(regulator_of(&solution.x, digits))
+    }
+}
+
+/// log(x + y*sqrt(N)) for a solution (x, y) of x^2 - N*y^2 = 1, such as the
+/// one `chakravala` returns. Only x is needed, since y*sqrt(N) = sqrt(x^2 - 1).
+/// Computed in fixed-point integer arithmetic so x may have any number of digits.
+pub fn regulator_of(x: &BigInt, digits: u32) -> Regulator {
+
+    // x + y*sqrt(N) = x + sqrt(x^2 - 1) lies in [2^t, 2^(t+2)) for t = bits(x) - 1,
+    // so the regulator is t*log(2) + log(z) with z in [1, 4). The guard bits
+    // cover the error in log(2) multiplied by t.
+    let t = x.bits().saturating_sub(1);
+    let guard = 64 + (u64::BITS - t.leading_zeros()) as u64;
+    let prec = (digits as u64 * 3322).div_ceil(1000) + guard;
+
+    // z as a fixed-point number with `prec` fractional bits.
+    let shift = prec.saturating_sub(t);
+    let scaled_sum = (x << shift) + ((x * x - 1u32) << (2 * shift)).sqrt();
+    let mut z = scaled_sum >> (t + shift - prec);
+
+    let one = BigInt::one() << prec;
+    let ln2 = ln_fixed(&(&one * 2u32), &one, prec);
+
+    let mut log = &ln2 * t;
+    if z >= &one * 2u32 {
+        z >>= 1;
+        log += &ln2;
+    }
+    log += ln_fixed(&z, &one, prec);
+
+    Regulator {
+        scaled: (log * BigInt::from(10u32).pow(digits)) >> prec,
+        digits,
+    }
+}
+
+/// log(z) for fixed-point z in [1, 2], as 2*atanh((z - 1) / (z + 1)).
+fn ln_fixed(z: &BigInt, one: &BigInt, prec: u64) -> BigInt {
+    let w = ((z - one) << prec) / (z + one);
+    let w2 = (&w * &w) >> prec;
+
+    // atanh(w) = w + w^3/3 + w^5/5 + ...
+    let mut sum = BigInt::zero();
+    let mut power = w;
+    let mut k = 1u32;
+    while !power.is_zero() {
+        sum += &power / k;
+        power = (power * &w2) >> prec;
+        k += 2;
+    }
+    sum * 2u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::chakravala;
+
+    #[test]
+    fn regulator_of_61() {
+        assert_eq!(regulator(61, 30).unwrap().to_string(), "21.985310765318625155555078930785");
+
+        let (x, _) = chakravala(61).unwrap();
+        assert_eq!(regulator_of(&x, 5).to_string(), "21.98531");
+        assert_eq!(regulator(2, 0).unwrap().to_string(), "1");
+    }
+}
+
@@ -0,0 +1,273 @@
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_traits::{One, Signed};
+
+use crate::int::CycleInt;
+use crate::compact::compact_step;
+use crate::options::SolveOptions;
+use crate::quadratic::{PellTriple, QuadInt};
+use crate::strategy::MultiplierStrategy;
+
+/// Closed forms that finish the cycle early: Brahmagupta's once k is -1, +-2
+/// or +-4, and the mirror image of the first half once the cycle reaches its
+/// midpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shortcut {
+    /// (a + b*sqrt(N))^2.
+    MinusOne,
+    /// (a + b*sqrt(N))^2 / 2, for k = 2 or k = -2.
+    Two,
+    /// ((a + b*sqrt(N)) / 2)^3 with a and b odd.
+    PlusFour,
+    /// ((a + b*sqrt(N)) / 2)^6 with a and b odd.
+    MinusFour,
+    /// (a + b*sqrt(N))^2 / |k|, when the cycle is symmetric about this triple.
+    Midpoint,
+    /// (a + b*sqrt(N))^2 * (m + sqrt(N)) / k^2, when the cycle is symmetric
+    /// about the next step, whose multiplier m has m^2 - N = k^2.
+    MidpointStep,
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let k = match self {
+            Shortcut::MinusOne => "-1",
+            Shortcut::Two => "+-2",
+            Shortcut::PlusFour => "4",
+            Shortcut::MinusFour => "-4",
+            Shortcut::Midpoint => return f.write_str("midpoint"),
+            Shortcut::MidpointStep => return f.write_str("midpoint step"),
+        };
+        write!(f, "k = {}", k)
+    }
+}
+
+/// Which shortcut finished a run, and how many cycle steps it skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShortcutReport {
+    pub shortcut: Shortcut,
+    /// Steps taken before the shortcut fired.
+    pub at_step: usize,
+    /// Steps the full cycle would still have needed to reach k = 1, or None
+    /// if counting them was cut short by the deadline or the cancel token.
+    pub saved: Option<usize>,
+}
+
+impl Shortcut {
+    /// The shortcut that applies to the triple (a, b, k), if any.
+    ///
+    /// With k = +-4 and a even, N is divisible by 4 and (a + b*sqrt(N)) / 2 is
+    /// not integral over Z[sqrt(N)], so the cycle carries on. a and b are never
+    /// both even, since every step keeps gcd(a, b) = 1.
+    pub fn detect<T: CycleInt>(a: &T, b: &T, k: &T) -> Option<Shortcut> {
+        let two = T::one() + T::one();
+        let four = two.clone() + two.clone();
+        let odd = a.is_odd() && b.is_odd();
+
+        if *k == -T::one() {
+            Some(Shortcut::MinusOne)
+        } else if k.abs() == two {
+            Some(Shortcut::Two)
+        } else if *k == four && odd {
+            Some(Shortcut::PlusFour)
+        } else if *k == -four && odd {
+            Some(Shortcut::MinusFour)
+        } else {
+            None
+        }
+    }
+
+    /// The midpoint shortcut that applies to a triple with norm `k`, given the
+    /// multiplier `last_m` that led to it and the multiplier `m` that leads on.
+    ///
+    /// The k and m of a cycle read the same backwards, so the cycle is
+    /// symmetric about this triple when m repeats `last_m`, and about the next
+    /// step when that step leads to the same k again. Only a symmetric cycle
+    /// such as the one `MinimizeNorm` or `Ayyangar` runs has a midpoint.
+    pub fn midpoint<T: CycleInt>(n: &T, k: &T, last_m: &T, m: &T) -> Option<Shortcut> {
+        if m == last_m {
+            Some(Shortcut::Midpoint)
+        } else if m.checked_mul(m)?.checked_sub(n)? == k.checked_mul(k)? {
+            Some(Shortcut::MidpointStep)
+        } else {
+            None
+        }
+    }
+
+    /// Steps the full cycle still needs after a shortcut at step `at_step`,
+    /// read off the symmetry, or None for k = +-4.
+    ///
+    /// k = -1 and k = +-2 are only reached at the midpoint. The cycle is
+    /// symmetric from (1, 0, 1), one step before the first triple, when
+    /// `symmetric_start` says a0 is the multiplier the strategy picks at k = 1.
+    /// Otherwise it takes one more step to fall into the symmetric cycle.
+    pub(crate) fn mirrored_steps(self, at_step: usize, symmetric_start: bool) -> Option<usize> {
+        let offset = if symmetric_start { 1 } else { 2 };
+        match self {
+            Shortcut::MinusOne | Shortcut::Two | Shortcut::Midpoint => Some(at_step + offset),
+            Shortcut::MidpointStep => Some(at_step + offset + 1),
+            _ => None,
+        }
+    }
+
+    /// Jumps from `triple` straight to the solution of x^2 - N*y^2 = 1.
+    ///
+    /// The midpoint shortcuts compose the first half of the cycle with its
+    /// conjugate, since the second half is the first one mirrored.
+    pub fn apply(self, triple: &PellTriple) -> (BigInt, BigInt) {
+        let PellTriple { a, b, k, .. } = triple;
+        match self {
+            Shortcut::MinusOne => {
+                let QuadInt { a: x, b: y, .. } = triple.value().pow(2);
+                (x, y)
+            }
+            Shortcut::Two => {
+                let QuadInt { a: x, b: y, .. } = triple.value().pow(2) / &BigInt::from(2);
+                (x, y)
+            }
+            Shortcut::PlusFour => {
+                // x = a(a^2 - 3)/2, y = b(a^2 - 1)/2
+                let a2 = a * a;
+                ((a * (&a2 - 3u32)) / 2u32, (b * (&a2 - 1u32)) / 2u32)
+            }
+            Shortcut::MinusFour => {
+                // x = (a^2 + 2)((a^2 + 1)(a^2 + 3) - 2)/2, y = ab(a^2 + 1)(a^2 + 3)/2
+                let a2 = a * a;
+                let product = (&a2 + 1u32) * (&a2 + 3u32);
+                ((&a2 + 2u32) * (&product - 2u32) / 2u32, a * b * product / 2u32)
+            }
+            Shortcut::Midpoint => {
+                let QuadInt { a: x, b: y, .. } = triple.value().pow(2) / &k.abs();
+                (x, y)
+            }
+            Shortcut::MidpointStep => {
+                // m^2 = N + k^2 picks out m.
+                let n = triple.n();
+                let step = QuadInt::new((n + k * k).sqrt(), 1, n.clone());
+                let QuadInt { a: x, b: y, .. } = triple.value().pow(2) * step / &(k * k);
+                (x, y)
+            }
+        }
+    }
+}
+
+/// Number of steps from norm `k` to k = 1, running the cycle on m and k
+/// alone. `m` is the multiplier of the step that produced k. None if the
+/// deadline passes or the run is cancelled first.
+pub(crate) fn remaining_steps<S>(
+    strategy: &S,
+    options: &SolveOptions,
+    n: &BigInt,
+    root: &BigInt,
+    m: &BigInt,
+    k: &BigInt,
+) -> Option<usize>
+where
+    S: MultiplierStrategy,
+{
+    let (mut m, mut k) = (m.clone(), k.clone());
+    let mut steps = 0;
+    while !k.is_one() {
+        if options.interrupted().is_some() {
+            return None;
+        }
+        (m, k) = compact_step(strategy, n, root, &m, &k);
+        steps += 1;
+    }
+    Some(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::OnceLock;
+
+    use super::*;
+    use crate::backend::{ContinuedFraction, PellBackend};
+    use crate::options::CancelToken;
+    use crate::solver::{PellEquation, Solver};
+    use crate::strategy::{Ayyangar, MinimizeNewK, MinimizeNorm, NearestRoot};
+
+    /// Solves every non-square N < 3000 with and without shortcuts, checks
+    /// both against the continued fraction, and returns the shortcuts taken.
+    fn shortcuts_taken<S: MultiplierStrategy + Copy>(strategy: S) -> HashSet<Shortcut> {
+        let mut taken = HashSet::new();
+        for n in 2u32..3000 {
+            let eq = PellEquation::new(n);
+            let Ok(expected) = ContinuedFraction.solve(&eq) else { continue };
+            let full = Solver::new().strategy(strategy).full_cycle(true).solve(&eq).unwrap();
+            let fast = Solver::new().strategy(strategy).solve(&eq).unwrap();
+
+            assert_eq!((&full.x, &full.y), (&expected.x, &expected.y), "N={}", n);
+            assert_eq!((&fast.x, &fast.y, &fast.negative), (&full.x, &full.y, &full.negative), "N={}", n);
+            if let Some(report) = fast.shortcut {
+                assert_eq!(report.at_step, fast.steps, "N={}", n);
+                assert_eq!(report.saved, Some(full.steps - report.at_step), "N={}", n);
+                taken.insert(report.shortcut);
+            }
+        }
+        taken
+    }
+
+    /// `shortcuts_taken` for each built-in strategy, the default first, run
+    /// once and shared by the tests.
+    fn taken_by_each_strategy() -> &'static [HashSet<Shortcut>; 4] {
+        static TAKEN: OnceLock<[HashSet<Shortcut>; 4]> = OnceLock::new();
+        TAKEN.get_or_init(|| {
+            [
+                shortcuts_taken(MinimizeNorm),
+                shortcuts_taken(MinimizeNewK),
+                shortcuts_taken(Ayyangar),
+                shortcuts_taken(NearestRoot),
+            ]
+        })
+    }
+
+    #[test]
+    fn brahmagupta_shortcuts_match_the_full_cycle() {
+        let brahmagupta = [Shortcut::MinusOne, Shortcut::Two, Shortcut::PlusFour, Shortcut::MinusFour];
+        for taken in taken_by_each_strategy() {
+            assert!(brahmagupta.iter().all(|shortcut| taken.contains(shortcut)));
+        }
+    }
+
+    #[test]
+    fn midpoint_shortcuts_match_the_full_cycle() {
+        // Both kinds of midpoint occur below 3000 for the symmetric strategies. Ayyangar's
+        // cycle never has two equal k in a row, so it only ever has the first.
+        let [norm, new_k, ayyangar, _] = taken_by_each_strategy();
+        let both = [Shortcut::Midpoint, Shortcut::MidpointStep];
+        assert!(both.iter().all(|shortcut| norm.contains(shortcut)));
+        assert!(both.iter().all(|shortcut| new_k.contains(shortcut)));
+        assert!(ayyangar.contains(&Shortcut::Midpoint));
+    }
+
+    #[test]
+    fn midpoints_of_small_cycles() {
+        // N = 43 reaches (59, 9, -2) with m = 7 and leaves it with m = 7 again.
+        assert_eq!(Shortcut::midpoint(&43i64, &-2, &7, &7), Some(Shortcut::Midpoint));
+        let (x, y) = Shortcut::Midpoint.apply(&PellTriple::new(59, 9, 43));
+        assert_eq!((x, y), (BigInt::from(3482), BigInt::from(531)));
+
+        // N = 91 leaves (19, 2, -3) with m = 10, and 10^2 - 91 = (-3)^2 leads to k = -3 again.
+        assert_eq!(Shortcut::midpoint(&91i64, &-3, &8, &10), Some(Shortcut::MidpointStep));
+        let (x, y) = Shortcut::MidpointStep.apply(&PellTriple::new(19, 2, 91));
+        assert_eq!((x, y), (BigInt::from(1574), BigInt::from(165)));
+
+        // N = 13 leaves (7, 2, -3) with m = 4, which is neither.
+        assert_eq!(Shortcut::midpoint(&13i64, &-3, &2, &4), None);
+    }
+
+    #[test]
+    fn counting_saved_steps_is_best_effort() {
+        // N = 61 reaches k = -4 after m = 7, twelve steps short of k = 1.
+        let (n, root, m, k) = (BigInt::from(61), BigInt::from(7), BigInt::from(7), BigInt::from(-4));
+        assert_eq!(remaining_steps(&MinimizeNorm, &SolveOptions::new(), &n, &root, &m, &k), Some(12));
+
+        let token = CancelToken::new();
+        token.cancel();
+        let cancelled = SolveOptions::new().cancel_token(token);
+        assert_eq!(remaining_steps(&MinimizeNorm, &cancelled, &n, &root, &m, &k), None);
+    }
+}
@@ -0,0 +1,49 @@
+use num_bigint::BigInt;
+
+use crate::error::PellError;
+use crate::quadratic::QuadInt;
+use crate::solver::{PellEquation, Solution, Solver};
+use crate::strategy::MultiplierStrategy;
+
+/// All positive solutions (x_n, y_n) of x^2 - N*y^2 = 1, in increasing order.
+///
+/// x_n + y_n*sqrt(N) = (x_1 + y_1*sqrt(N))^n, so each one is the previous one
+/// composed with the fundamental solution by Brahmagupta's identity.
+#[derive(Debug, Clone)]
+pub struct PellSolutions {
+    fundamental: QuadInt,
+    current: QuadInt,
+}
+
+impl PellSolutions {
+    pub fn new(eq: &PellEquation, fundamental: &Solution) -> Self {
+        PellSolutions {
+            fundamental: QuadInt::new(fundamental.x.clone(), fundamental.y.clone(), eq.n().clone()),
+            current: QuadInt::one(eq.n().clone()),
+        }
+    }
+}
+
+impl Iterator for PellSolutions {
+    type Item = (BigInt, BigInt);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.current = &self.current * &self.fundamental;
+        Some((self.current.a.clone(), self.current.b.clone()))
+    }
+
+    /// Skips ahead by binary exponentiation in Z[sqrt(N)], so `nth(9_999)`
+    /// returns the 10,000th solution without producing the ones before it.
+    fn nth(&mut self, skip: usize) -> Option<Self::Item> {
+        self.current = &self.current * &self.fundamental.pow(skip as u64 + 1);
+        Some((self.current.a.clone(), self.current.b.clone()))
+    }
+}
+
+impl<S: MultiplierStrategy> Solver<S> {
+    /// Solves `eq` and returns an iterator over all of its positive solutions.
+    pub fn solutions(&self, eq: &PellEquation) -> Result<PellSolutions, PellError> {
+        let fundamental = self.solve(eq)?;
+        Ok(PellSolutions::new(eq, &fundamental))
+    }
+}
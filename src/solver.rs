@@ -0,0 +1,536 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, Zero};
+
+use crate::error::{PartialState, PellError};
+use crate::int::CycleInt;
+use crate::kuttaka;
+use crate::options::SolveOptions;
+use crate::quadratic::PellTriple;
+use crate::shortcut::{Shortcut, ShortcutReport, remaining_steps};
+use crate::strategy::{MinimizeNorm, MultiplierStrategy};
+
+/// Pell's equation x^2 - N*y^2 = 1 for a fixed N.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PellEquation {
+    n: BigInt,
+}
+
+impl PellEquation {
+    /// Accepts any integer convertible into `BigInt` (u32, u64, u128, BigInt, ...).
+    pub fn new(n: impl Into<BigInt>) -> Self {
+        PellEquation { n: n.into() }
+    }
+
+    pub fn n(&self) -> &BigInt {
+        &self.n
+    }
+
+    /// Returns true if (x, y) satisfies x^2 - N*y^2 = 1.
+    pub fn is_solution(&self, x: &BigInt, y: &BigInt) -> bool {
+        x * x - &self.n * y * y == BigInt::one()
+    }
+
+    /// floor(sqrt(N)), or an error if N is not positive or is a perfect square.
+    pub(crate) fn checked_root(&self) -> Result<BigInt, PellError> {
+        if !self.n.is_positive() {
+            return Err(PellError::ZeroOrNegativeN { n: self.n.clone() });
+        }
+
+        let root = self.n.sqrt();
+        if &root * &root == self.n {
+            return Err(PellError::PerfectSquare { n: self.n.clone(), root });
+        }
+        Ok(root)
+    }
+
+    /// Returns true if (x, y) satisfies x^2 - N*y^2 = -1.
+    pub fn is_negative_solution(&self, x: &BigInt, y: &BigInt) -> bool {
+        x * x - &self.n * y * y == -BigInt::one()
+    }
+}
+
+/// Fundamental solution of x^2 - N*y^2 = 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub x: BigInt,
+    pub y: BigInt,
+    /// Number of Chakravala steps taken after the starting triple.
+    pub steps: usize,
+    /// Fundamental solution of x^2 - N*y^2 = -1, if that equation is solvable.
+    pub negative: Option<(BigInt, BigInt)>,
+    /// The closed form that finished the cycle early, if one did.
+    pub shortcut: Option<ShortcutReport>,
+}
+
+impl Solution {
+    /// The trivial solution (1, 0), which is the only one when N is a perfect square.
+    pub fn trivial() -> Self {
+        Solution {
+            x: BigInt::one(),
+            y: BigInt::zero(),
+            steps: 0,
+            negative: None,
+            shortcut: None,
+        }
+    }
+
+    pub fn is_trivial(&self) -> bool {
+        self.y.is_zero()
+    }
+}
+
+/// Solves Pell's equation using the Chakravala method, choosing each
+/// multiplier with the strategy S.
+#[derive(Debug, Clone, Default)]
+pub struct Solver<S = MinimizeNorm> {
+    pub(crate) options: SolveOptions,
+    pub(crate) threads: Option<usize>,
+    trivial_for_squares: bool,
+    full_cycle: bool,
+    pub(crate) strategy: S,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Solver::default()
+    }
+}
+
+impl<S: MultiplierStrategy> Solver<S> {
+    /// Chooses multipliers with `strategy` instead.
+    pub fn strategy<R: MultiplierStrategy>(self, strategy: R) -> Solver<R> {
+        Solver {
+            options: self.options,
+            threads: self.threads,
+            trivial_for_squares: self.trivial_for_squares,
+            full_cycle: self.full_cycle,
+            strategy,
+        }
+    }
+
+    /// Bounds every run by `options`: a step limit, a deadline and a cancel token.
+    pub fn options(mut self, options: SolveOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Gives up with `IterationLimitExceeded` after this many steps, keeping
+    /// the other options.
+    pub fn max_steps(mut self, steps: usize) -> Self {
+        self.options = self.options.max_steps(steps);
+        self
+    }
+
+    /// Number of threads `solve_range` uses. Defaults to the available parallelism.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Returns the trivial solution (1, 0) for a perfect square N instead of
+    /// `PellError::PerfectSquare`.
+    pub fn trivial_for_squares(mut self, enabled: bool) -> Self {
+        self.trivial_for_squares = enabled;
+        self
+    }
+
+    /// Runs the cycle all the way to k = 1 instead of finishing it with a
+    /// Brahmagupta shortcut once k is -1, +-2 or +-4, or from its midpoint.
+    pub fn full_cycle(mut self, enabled: bool) -> Self {
+        self.full_cycle = enabled;
+        self
+    }
+
+    /// Returns the fundamental solution of x^2 - N*y^2 = 1.
+    pub fn solve(&self, eq: &PellEquation) -> Result<Solution, PellError> {
+        self.run(eq, None)
+    }
+
+    /// Like `solve`, but calls `observe` with every step of the cycle. The
+    /// cycle runs all the way to k = 1, without shortcuts.
+    pub fn solve_with(&self, eq: &PellEquation, mut observe: impl FnMut(&Step)) -> Result<Solution, PellError> {
+        self.run(eq, Some(&mut observe))
+    }
+
+    /// Returns an iterator over the steps of the cycle for `eq`. It yields
+    /// the error and ends once one of the solver's options stops it.
+    pub fn trace(&self, eq: &PellEquation) -> Result<Cycle<S>, PellError>
+    where
+        S: Clone,
+    {
+        Cycle::new(eq, self.strategy.clone(), self.options.clone())
+    }
+
+    fn run(&self, eq: &PellEquation, mut observe: Option<&mut dyn FnMut(&Step)>) -> Result<Solution, PellError> {
+        let mut cycle = match Cycle::new(eq, &self.strategy, self.options.clone()) {
+            Err(PellError::PerfectSquare { .. }) if self.trivial_for_squares => {
+                return Ok(Solution::trivial());
+            }
+            result => result?,
+        };
+        let mut negative = None;
+        let mut shortcut = None;
+        // An observer sees every step, so only an unobserved run takes shortcuts.
+        let shortcuts = !self.full_cycle && observe.is_none();
+        let root = &cycle.root;
+        let symmetric_start =
+            self.strategy.choose(cycle.current.n(), &BigInt::one(), root.clone(), root + 1u32) == Some(cycle.current.a.clone());
+
+        // Small N runs on i128 until a step would overflow; BigInt takes over from there.
+        if observe.is_none() {
+            self.run_fast::<i128>(&mut cycle, &mut negative);
+        }
+
+        // 3. Main Loop
+        // Cycle until k = 1.
+        // Once k is -1, +-2 or +-4, Brahmagupta's composition finishes in closed form,
+        // and once the cycle turns back on itself, its first half mirrored finishes it.
+        while !cycle.current.k.is_one() {
+            // The first triple with k = -1 solves the negative equation.
+            if negative.is_none() && cycle.current.k == -BigInt::one() {
+                negative = Some((cycle.current.a.clone(), cycle.current.b.clone()));
+            }
+
+            let m = cycle.multiplier()?;
+            let PellTriple { a, b, k, .. } = &cycle.current;
+            let n = cycle.current.n();
+            if shortcuts
+                && let Some(found) = Shortcut::detect(a, b, k).or_else(|| Shortcut::midpoint(n, k, &cycle.last_m, &m))
+            {
+                // Only a unit of norm 1 ends the cycle, which rules out a false midpoint.
+                let (x, y) = found.apply(&cycle.current);
+                if !y.is_zero() && eq.is_solution(&x, &y) {
+                    // Counting the saved steps is best-effort: the solution stands even if it is cut short.
+                    let saved = found
+                        .mirrored_steps(cycle.steps, symmetric_start)
+                        .or_else(|| remaining_steps(&self.strategy, &self.options, n, &cycle.root, &cycle.last_m, k));
+                    shortcut = Some(ShortcutReport { shortcut: found, at_step: cycle.steps, saved });
+
+                    cycle.current = PellTriple::new(x, y, n.clone());
+                    break;
+                }
+            }
+
+            if let Some(stop) = self.options.stop(cycle.steps) {
+                return Err(stop.error(cycle.state()));
+            }
+
+            match observe.as_mut() {
+                Some(observe) => observe(&cycle.step(m)),
+                None => cycle.advance(m),
+            }
+        }
+
+        // If the cycle stepped over k = -1, the negative solution (u, v) can still
+        // be recovered from x = 2u^2 + 1, y = 2uv.
+        if negative.is_none() {
+            negative = negative_from_fundamental(cycle.current.n(), &cycle.current.a, &cycle.current.b);
+        }
+
+        Ok(Solution {
+            x: cycle.current.a,
+            y: cycle.current.b,
+            steps: cycle.steps,
+            negative,
+            shortcut,
+        })
+    }
+
+    /// Runs the cycle on T until k = 1, an option stops it, or a step T cannot
+    /// hold, then hands the triple back to `cycle`.
+    fn run_fast<T: CycleInt>(&self, cycle: &mut Cycle<&S>, negative: &mut Option<(BigInt, BigInt)>) {
+        let convert = |v: &BigInt| T::from_bigint(v);
+        let (Some(n), Some(root), Some(mut a), Some(mut b), Some(mut k), Some(mut last_m)) = (
+            convert(cycle.current.n()),
+            convert(&cycle.root),
+            convert(&cycle.current.a),
+            convert(&cycle.current.b),
+            convert(&cycle.current.k),
+            convert(&cycle.last_m),
+        ) else {
+            return;
+        };
+        let mut steps = cycle.steps;
+
+        while !k.is_one() {
+            if negative.is_none() && k == -T::one() {
+                *negative = Some((a.to_bigint(), b.to_bigint()));
+            }
+
+            let Some(m) = find_optimal_m(&self.strategy, &n, &root, &a, &b, &k) else { break };
+            // Shortcuts are taken by the BigInt loop.
+            let shortcut = !self.full_cycle
+                && (Shortcut::detect(&a, &b, &k).is_some() || Shortcut::midpoint(&n, &k, &last_m, &m).is_some());
+            if shortcut || self.options.stop(steps).is_some() {
+                break;
+            }
+
+            let Some((new_a, new_b, new_k)) = samasa(&n, &a, &b, &k, &m) else { break };
+            a = new_a;
+            b = new_b;
+            k = new_k;
+            last_m = m;
+            steps += 1;
+        }
+
+        cycle.current.a = a.to_bigint();
+        cycle.current.b = b.to_bigint();
+        cycle.current.k = k.to_bigint();
+        cycle.last_m = last_m.to_bigint();
+        cycle.steps = steps;
+    }
+
+    /// Returns the fundamental solution of x^2 - N*y^2 = -1, or None if that
+    /// equation has no solution. Both come from the same pass as `solve`.
+    pub fn solve_negative(&self, eq: &PellEquation) -> Result<Option<(BigInt, BigInt)>, PellError> {
+        self.solve(eq).map(|s| s.negative)
+    }
+}
+
+/// One step of the Chakravala cycle: the triple (a, b, k), the chosen m and
+/// the triple it leads to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub a: BigInt,
+    pub b: BigInt,
+    pub k: BigInt,
+    pub m: BigInt,
+    pub new_a: BigInt,
+    pub new_b: BigInt,
+    pub new_k: BigInt,
+}
+
+/// Iterator over the steps of the Chakravala cycle, ending once k = 1.
+#[derive(Debug, Clone)]
+pub struct Cycle<S = MinimizeNorm> {
+    root: BigInt,
+    current: PellTriple,
+    // The m of the last step, or a0 before the first; the next m is -last_m (mod |k|).
+    last_m: BigInt,
+    steps: usize,
+    failed: bool,
+    strategy: S,
+    options: SolveOptions,
+}
+
+impl<S: MultiplierStrategy> Cycle<S> {
+    fn new(eq: &PellEquation, strategy: S, options: SolveOptions) -> Result<Self, PellError> {
+        // 1. Check if N is a perfect square (only the trivial solution if so)
+        let root = eq.checked_root()?;
+        let n = eq.n().clone();
+
+        // 2. Initialisation
+        // We want a^2 - N*b^2 = k.
+        // Standard start: b = 1, a = closest integer to sqrt(N).
+        let a = closest_root(&n, root.clone());
+        let current = PellTriple::new(a.clone(), 1, n);
+
+        Ok(Cycle {
+            root,
+            current,
+            last_m: a,
+            steps: 0,
+            failed: false,
+            strategy,
+            options,
+        })
+    }
+
+    /// The current triple (a, b, k), with a^2 - N*b^2 = k.
+    pub fn triple(&self) -> (&BigInt, &BigInt, &BigInt) {
+        (&self.current.a, &self.current.b, &self.current.k)
+    }
+
+    /// Number of steps taken so far.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// The current triple and step count, for an error that stops the cycle here.
+    fn state(&self) -> PartialState {
+        let PellTriple { a, b, k, .. } = self.current.clone();
+        PartialState { a, b, k, steps: self.steps }
+    }
+
+    /// The m of the next step.
+    fn multiplier(&self) -> Result<BigInt, PellError> {
+        // Find m such that:
+        // 1. (a + b*m) is divisible by k
+        // 2. the strategy prefers it, by default for minimizing |m^2 - N|
+        let PellTriple { a, b, k, .. } = &self.current;
+        find_optimal_m(&self.strategy, self.current.n(), &self.root, a, b, k).ok_or_else(|| PellError::NoValidMultiplier {
+            a: a.clone(),
+            b: b.clone(),
+            k: k.clone(),
+        })
+    }
+
+    /// Moves to the next triple using the multiplier m.
+    fn advance(&mut self, m: BigInt) {
+        // Compose with (m, 1, m^2 - N), then divide through by |k|:
+        // new_k = (m^2 - N) / k
+        // new_a = (a*m + N*b) / |k|
+        // new_b = (a + b*m) / |k|
+        let abs_k = self.current.k.abs();
+        self.current = &(&self.current * &PellTriple::new(m.clone(), 1, self.current.n().clone())) / &abs_k;
+        self.last_m = m;
+        self.steps += 1;
+    }
+
+    fn step(&mut self, m: BigInt) -> Step {
+        let PellTriple { a, b, k, .. } = self.current.clone();
+        self.advance(m.clone());
+
+        Step {
+            a,
+            b,
+            k,
+            m,
+            new_a: self.current.a.clone(),
+            new_b: self.current.b.clone(),
+            new_k: self.current.k.clone(),
+        }
+    }
+}
+
+impl<S: MultiplierStrategy> Iterator for Cycle<S> {
+    type Item = Result<Step, PellError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.current.k.is_one() {
+            return None;
+        }
+
+        let step = match self.options.stop(self.steps) {
+            Some(stop) => Err(stop.error(self.state())),
+            None => self.multiplier().map(|m| self.step(m)),
+        };
+        self.failed = step.is_err();
+        Some(step)
+    }
+}
+
+/// Recovers (u, v) with u^2 - N*v^2 = -1 from the fundamental solution (x, y),
+/// using (u + v*sqrt(N))^2 = x + y*sqrt(N).
+fn negative_from_fundamental(n: &BigInt, x: &BigInt, y: &BigInt) -> Option<(BigInt, BigInt)> {
+    let half = (x - 1u32) / 2u32;
+    let u = half.sqrt();
+    if u.is_zero() || &u * &u != half {
+        return None;
+    }
+
+    let (v, rem) = y.div_rem(&(&u * 2u32));
+    if !rem.is_zero() || &u * &u - n * &v * &v != -BigInt::one() {
+        return None;
+    }
+    Some((u, v))
+}
+
+/// Solves x^2 - N*y^2 = 1 using the Chakravala method.
+/// Returns (x, y).
+pub fn chakravala(n: impl Into<BigInt>) -> Result<(BigInt, BigInt), PellError> {
+    Solver::new()
+        .solve(&PellEquation::new(n))
+        .map(|s| (s.x, s.y))
+}
+
+/// The integer closest to sqrt(N), given `root` = floor(sqrt(N)).
+pub(crate) fn closest_root(n: &BigInt, root: BigInt) -> BigInt {
+    // Adjust 'a' to be the closest integer to sqrt(N)
+    // currently a = floor(sqrt(N)). Check if ceil(sqrt(N)) is closer.
+    let diff1 = (n - &root * &root).abs();
+    let root_plus = &root + &BigInt::one();
+    let diff2 = (&root_plus * &root_plus - n).abs();
+
+    if diff2 < diff1 { root_plus } else { root }
+}
+
+/// Finds 'm' such that (a + b*m) % k == 0, as chosen by `strategy`.
+/// Returns None if T overflows or b has no inverse modulo |k|.
+fn find_optimal_m<S, T>(strategy: &S, n: &T, root: &T, a: &T, b: &T, k: &T) -> Option<T>
+where
+    S: MultiplierStrategy,
+    T: CycleInt,
+{
+    // b*m = -a (mod |k|) is a linear congruence in m, solved by the kuttaka.
+    // Its solutions form a single class modulo |k|, since gcd(a, b) = 1 is
+    // preserved by every step.
+    let class = kuttaka::solve_congruence(b, &-a.clone(), &k.abs())?;
+
+    pick_in_class(strategy, n, root, k, &class.residue, &class.modulus)
+}
+
+/// Returns the member of `residue` (mod `modulus`) that `strategy` picks for
+/// the triple with norm `k`, given `root` = floor(sqrt(N)). Only the members
+/// on either side of sqrt(N) are offered.
+pub(crate) fn pick_in_class<S: MultiplierStrategy, T: CycleInt>(
+    strategy: &S,
+    n: &T,
+    root: &T,
+    k: &T,
+    residue: &T,
+    modulus: &T,
+) -> Option<T> {
+    // Largest member <= floor(sqrt(N)), and the next one above it.
+    let below = root.clone() - (root.clone() - residue.clone()).mod_floor(modulus);
+    let above = below.checked_add(modulus)?;
+
+    if !below.is_positive() {
+        return Some(above);
+    }
+
+    strategy.choose(n, k, below, above)
+}
+
+/// The step of `Cycle::advance` in checked arithmetic: compose (a, b, k) with
+/// (m, 1, m^2 - N) and divide by |k|. Returns None if T overflows.
+fn samasa<T: CycleInt>(n: &T, a: &T, b: &T, k: &T, m: &T) -> Option<(T, T, T)> {
+    let abs_k = k.abs();
+
+    let new_k = m.checked_mul(m)?.checked_sub(n)? / k.clone();
+    let new_a = a.checked_mul(m)?.checked_add(&n.checked_mul(b)?)? / abs_k.clone();
+    let new_b = a.checked_add(&b.checked_mul(m)?)? / abs_k;
+
+    Some((new_a, new_b, new_k))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::CancelToken;
+
+    #[test]
+    fn observer_sees_every_step_of_the_cycle() {
+        for n in 2u32..500 {
+            let eq = PellEquation::new(n);
+            let Ok(cycle) = Solver::new().trace(&eq) else { continue };
+
+            let mut observed = 0;
+            let solution = Solver::new().solve_with(&eq, |_| observed += 1).unwrap();
+            assert_eq!(observed, cycle.count(), "N={}", n);
+            assert_eq!(solution.steps, observed, "N={}", n);
+        }
+    }
+
+    #[test]
+    fn options_reach_every_entry_point() {
+        // N = 94 takes 9 steps; the trace stops after 3 with the triple it reached.
+        let eq = PellEquation::new(94);
+        let steps: Vec<_> = Solver::new().max_steps(3).trace(&eq).unwrap().collect();
+        assert_eq!(steps.len(), 4);
+        assert!(steps[..3].iter().all(Result::is_ok));
+        let Err(PellError::IterationLimitExceeded { state }) = &steps[3] else { panic!("{:?}", steps[3]) };
+        assert_eq!(state.steps, 3);
+
+        let token = CancelToken::new();
+        token.cancel();
+        let solver = Solver::new().options(SolveOptions::new().cancel_token(token));
+        let cancelled = |result: Result<(), PellError>| matches!(result, Err(PellError::Cancelled { .. }));
+        assert!(cancelled(solver.estimate(&eq).map(drop)));
+        assert!(cancelled(solver.compare_strategies(&eq).map(drop)));
+        assert!(cancelled(solver.regulator(&eq, 10).map(drop)));
+        assert!(cancelled(solver.fundamental_unit(94).map(drop)));
+        assert!(cancelled(solver.fundamental_unit_unchecked(94).map(drop)));
+    }
+}
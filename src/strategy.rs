@@ -0,0 +1,151 @@
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::error::PellError;
+use crate::int::CycleInt;
+use crate::solver::{PellEquation, Solver};
+
+/// Chooses the multiplier m of each Chakravala step.
+///
+/// Every valid m lies in one residue class, and only its two members around
+/// sqrt(N) are ever worth taking: `below`, the largest one not above
+/// floor(sqrt(N)), and `above` = below + |k|. The solver takes `above` by
+/// itself when `below` is not positive.
+pub trait MultiplierStrategy {
+    fn name(&self) -> &'static str;
+
+    /// Returns `below` or `above` for the triple with norm `k`, or None if T overflows.
+    fn choose<T: CycleInt>(&self, n: &T, k: &T, below: T, above: T) -> Option<T>;
+}
+
+impl<S: MultiplierStrategy> MultiplierStrategy for &S {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn choose<T: CycleInt>(&self, n: &T, k: &T, below: T, above: T) -> Option<T> {
+        (**self).choose(n, k, below, above)
+    }
+}
+
+/// Minimizes |m^2 - N|, preferring `below` on a tie. This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MinimizeNorm;
+
+impl MultiplierStrategy for MinimizeNorm {
+    fn name(&self) -> &'static str {
+        "minimize-norm"
+    }
+
+    fn choose<T: CycleInt>(&self, n: &T, _k: &T, below: T, above: T) -> Option<T> {
+        let (d_below, d_above) = (distance(n, &below)?, distance(n, &above)?);
+        Some(if d_above < d_below { above } else { below })
+    }
+}
+
+/// Minimizes the next |k| = |m^2 - N| / |k|, preferring a negative next k on a tie.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MinimizeNewK;
+
+impl MultiplierStrategy for MinimizeNewK {
+    fn name(&self) -> &'static str {
+        "minimize-new-k"
+    }
+
+    fn choose<T: CycleInt>(&self, n: &T, k: &T, below: T, above: T) -> Option<T> {
+        let (d_below, d_above) = (distance(n, &below)?, distance(n, &above)?);
+        if d_below != d_above {
+            return Some(if d_above < d_below { above } else { below });
+        }
+        // m^2 - N is negative for `below`, so its next k has the opposite sign to k.
+        Some(if k.is_positive() { below } else { above })
+    }
+}
+
+/// Ayyangar's rule: always the largest member below sqrt(N).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ayyangar;
+
+impl MultiplierStrategy for Ayyangar {
+    fn name(&self) -> &'static str {
+        "ayyangar"
+    }
+
+    fn choose<T: CycleInt>(&self, _n: &T, _k: &T, below: T, _above: T) -> Option<T> {
+        Some(below)
+    }
+}
+
+/// The member nearest to sqrt(N) itself rather than nearest in m^2.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NearestRoot;
+
+impl MultiplierStrategy for NearestRoot {
+    fn name(&self) -> &'static str {
+        "nearest-root"
+    }
+
+    fn choose<T: CycleInt>(&self, n: &T, _k: &T, below: T, above: T) -> Option<T> {
+        // sqrt(N) - below < above - sqrt(N) exactly when 4N < (below + above)^2.
+        let sum = below.checked_add(&above)?;
+        let four_n = n.checked_add(n)?.checked_add(&n.checked_add(n)?)?;
+        Some(if four_n < sum.checked_mul(&sum)? { below } else { above })
+    }
+}
+
+/// |m^2 - N|.
+fn distance<T: CycleInt>(n: &T, m: &T) -> Option<T> {
+    m.checked_mul(m)?.checked_sub(n).map(|d| d.abs())
+}
+
+/// How one strategy's cycle went for one N.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrategyReport {
+    pub strategy: &'static str,
+    pub steps: usize,
+    /// Largest |k| met along the cycle.
+    pub max_k: BigInt,
+    /// Mean of |k| over the cycle.
+    pub mean_k: f64,
+    /// Largest multiplier m used.
+    pub max_m: BigInt,
+}
+
+impl<S: MultiplierStrategy> Solver<S> {
+    /// Runs the cycle for `eq` in compact form and summarizes its length and
+    /// the size of its intermediate values.
+    pub fn report(&self, eq: &PellEquation) -> Result<StrategyReport, PellError> {
+        let unit = self.solve_compact(eq)?;
+        let factors = unit.factors();
+
+        let max_k = factors.iter().map(|(_, k)| k).max().cloned().unwrap_or_default();
+        let max_m = factors.iter().map(|(m, _)| m).max().cloned().unwrap_or_else(|| unit.a0().clone());
+        let sum_k: f64 = factors.iter().map(|(_, k)| k.to_f64().unwrap_or(f64::INFINITY)).sum();
+        let mean_k = if factors.is_empty() { 0.0 } else { sum_k / factors.len() as f64 };
+
+        Ok(StrategyReport {
+            strategy: self.strategy.name(),
+            steps: unit.steps(),
+            max_k,
+            mean_k,
+            max_m,
+        })
+    }
+
+    /// Reports on every built-in strategy for `eq`, the default first, each
+    /// run with this solver's options.
+    pub fn compare_strategies(&self, eq: &PellEquation) -> Result<Vec<StrategyReport>, PellError> {
+        let solver = Solver::new().options(self.options.clone());
+        Ok(vec![
+            solver.report(eq)?,
+            solver.clone().strategy(MinimizeNewK).report(eq)?,
+            solver.clone().strategy(Ayyangar).report(eq)?,
+            solver.strategy(NearestRoot).report(eq)?,
+        ])
+    }
+}
+
+/// Reports on every built-in strategy for `eq`, the default first.
+pub fn compare_strategies(eq: &PellEquation) -> Result<Vec<StrategyReport>, PellError> {
+    Solver::new().compare_strategies(eq)
+}
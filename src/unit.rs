@@ -0,0 +1,154 @@
+use num_bigint::BigInt;
+use num_integer::{Integer, Roots};
+use num_traits::{ToPrimitive, Zero};
+
+use crate::error::PellError;
+use crate::solver::{PellEquation, Solver};
+use crate::strategy::MultiplierStrategy;
+
+/// Fundamental unit of the maximal order of Q(sqrt(d)).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FundamentalUnit {
+    pub d: BigInt,
+    /// The unit is (x + y*sqrt(d)) / 2 when `half` is set, otherwise x + y*sqrt(d).
+    pub x: BigInt,
+    pub y: BigInt,
+    pub half: bool,
+    /// Norm of the unit, +1 or -1.
+    pub norm: i8,
+    /// Index [O_K* : Z[sqrt(d)]*], either 1 or 3.
+    pub index: u32,
+}
+
+/// Returns the fundamental unit of Q(sqrt(d)) for a squarefree d > 1.
+/// Squarefreeness is only decided for d < 2^63; larger d are refused, and
+/// `fundamental_unit_unchecked` takes them on the caller's word.
+///
+/// The unit of Z[sqrt(d)] comes from the Pell equation (its negative form when
+/// solvable). For d = 5 (mod 8) the field unit may be a half-integer
+/// (u + v*sqrt(d)) / 2 from u^2 - d*v^2 = +-4, and then its cube is the Pell unit.
+pub fn fundamental_unit(d: impl Into<BigInt>) -> Result<FundamentalUnit, PellError> {
+    Solver::new().fundamental_unit(d)
+}
+
+/// Like `fundamental_unit`, but trusts the caller that d is squarefree, so it
+/// takes d of any size. For a d with a square factor the result is the unit of
+/// Z[sqrt(d)] or Z[(1 + sqrt(d)) / 2], which need not be that of the field.
+pub fn fundamental_unit_unchecked(d: impl Into<BigInt>) -> Result<FundamentalUnit, PellError> {
+    Solver::new().fundamental_unit_unchecked(d)
+}
+
+impl<S: MultiplierStrategy> Solver<S> {
+    /// Like `fundamental_unit`, solving the Pell equation with this solver.
+    pub fn fundamental_unit(&self, d: impl Into<BigInt>) -> Result<FundamentalUnit, PellError> {
+        let eq = PellEquation::new(d);
+        let d = eq.n().clone();
+        eq.checked_root()?;
+        let Some(small) = d.to_u64().filter(|&d| d < SQUAREFREE_LIMIT) else {
+            return Err(PellError::SquarefreeUnknown { d });
+        };
+        if !is_squarefree(small) {
+            return Err(PellError::NotSquarefree { d });
+        }
+        self.fundamental_unit_unchecked(d)
+    }
+
+    /// Like `fundamental_unit_unchecked`, solving the Pell equation with this solver.
+    pub fn fundamental_unit_unchecked(&self, d: impl Into<BigInt>) -> Result<FundamentalUnit, PellError> {
+        let eq = PellEquation::new(d);
+        let d = eq.n().clone();
+        let solution = self.solve(&eq)?;
+        let (x, y, norm) = match solution.negative {
+            Some((u, v)) => (u, v, -1),
+            None => (solution.x, solution.y, 1),
+        };
+
+        if (&d % 8u32) == BigInt::from(5)
+            && let Some((u, v)) = half_cube_root(&d, &x, &y, norm)
+        {
+            return Ok(FundamentalUnit { d, x: u, y: v, half: true, norm, index: 3 });
+        }
+
+        Ok(FundamentalUnit { d, x, y, half: false, norm, index: 1 })
+    }
+}
+
+/// Finds odd (u, v) with ((u + v*sqrt(d)) / 2)^3 = x + y*sqrt(d).
+fn half_cube_root(d: &BigInt, x: &BigInt, y: &BigInt, norm: i8) -> Option<(BigInt, BigInt)> {
+    // With u^2 - d*v^2 = 4*norm, the rational part of the cube is
+    // u*(u^2 - 3*norm) / 2, so u is within one of cbrt(2x).
+    let four_norm = BigInt::from(4 * norm);
+    let estimate = (x * 2u32).cbrt();
+
+    for u in [&estimate - 1u32, estimate.clone(), &estimate + 1u32] {
+        if !u.is_odd() || &u * (&u * &u - 3 * norm) != x * 2u32 {
+            continue;
+        }
+
+        let (v2, rem) = (&u * &u - &four_norm).div_rem(d);
+        let v = v2.sqrt();
+        if rem.is_zero() && &v * &v == v2 && &v * (&u * &u * 3u32 + d * &v * &v) == y * 8u32 {
+            return Some((u, v));
+        }
+    }
+    None
+}
+
+/// `is_squarefree` decides every d below this. Trial division runs up to
+/// cbrt(d) < 2^21, so it takes at most about two million divisions.
+const SQUAREFREE_LIMIT: u64 = 1 << 63;
+
+/// Trial division up to cbrt(d); what remains has at most two prime factors,
+/// so it is squarefree unless it is a perfect square.
+fn is_squarefree(d: u64) -> bool {
+    let mut rest = d;
+    let limit = d.cbrt();
+
+    for p in 2..=limit {
+        if rest.is_multiple_of(p) {
+            rest /= p;
+            if rest.is_multiple_of(p) {
+                return false;
+            }
+        }
+    }
+
+    let root = rest.sqrt();
+    rest == 1 || root * root != rest
+}
+
+#[cfg(test)]
+mod tests {
+    use num_traits::One;
+
+    use super::*;
+
+    fn unit(d: u64) -> (i64, i64, bool, i8, u32) {
+        let u = fundamental_unit(d).unwrap();
+        (u.x.to_i64().unwrap(), u.y.to_i64().unwrap(), u.half, u.norm, u.index)
+    }
+
+    #[test]
+    fn small_fields() {
+        // (1 + sqrt(5)) / 2, (3 + sqrt(13)) / 2 and (5 + sqrt(21)) / 2, whose cube is 55 + 12*sqrt(21).
+        assert_eq!(unit(5), (1, 1, true, -1, 3));
+        assert_eq!(unit(13), (3, 1, true, -1, 3));
+        assert_eq!(unit(21), (5, 1, true, 1, 3));
+        // 37 = 5 (mod 8), but 6 + sqrt(37) is already the field unit.
+        assert_eq!(unit(37), (6, 1, false, -1, 1));
+        assert_eq!(unit(2), (1, 1, false, -1, 1));
+        assert_eq!(unit(3), (2, 1, false, 1, 1));
+    }
+
+    #[test]
+    fn squarefree_check() {
+        assert_eq!(fundamental_unit(12), Err(PellError::NotSquarefree { d: BigInt::from(12) }));
+        assert!(matches!(fundamental_unit(1u128 << 64 | 1), Err(PellError::SquarefreeUnknown { .. })));
+
+        // d = 10^20 + 1 is past the check; 10^10 + sqrt(d) has norm -1.
+        let d = BigInt::from(10u32).pow(20) + 1u32;
+        let u = fundamental_unit_unchecked(d).unwrap();
+        assert_eq!((u.x, u.y, u.half, u.norm), (BigInt::from(10u32).pow(10), BigInt::one(), false, -1));
+    }
+}
+